@@ -1,177 +1,2019 @@
-use proc_macro2::{Ident, TokenStream};
-use quote::{format_ident, quote};
-use syn::{parse_macro_input, Data, DeriveInput, GenericArgument, LitStr, PathArguments, Type};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Expr, Field, Fields,
+    GenericArgument, GenericParam, Generics, Index, LitBool, LitStr, Member, Meta, Path,
+    PathArguments, ReturnType, Type, Visibility,
+};
+
+struct BuilderOpts {
+    name: Option<Ident>,
+    vis: Option<Visibility>,
+    constructor: Option<Ident>,
+    build_fn_name: Option<Ident>,
+    error: Option<Type>,
+    validate: Option<Path>,
+    async_finish: Option<Path>,
+    default: bool,
+    pattern: Pattern,
+    setter: SetterOpts,
+    typestate: bool,
+    serde: bool,
+    no_std: bool,
+    accessors: bool,
+    merge: bool,
+    // Where `apply_env` looks for fields: `<prefix><FIELD>`, with list items
+    // separated by `env_separator`.
+    env_prefix: Option<String>,
+    env_separator: Option<String>,
+    // Forwarded onto the builder struct.
+    derives: Vec<Path>,
+    attrs: Vec<Meta>,
+}
+
+// Options accepted inside `setter(...)`, both on the struct and on individual
+// fields. Unset options on a field inherit from the struct. `name` and `skip`
+// only apply to fields.
+#[derive(Default)]
+struct SetterOpts {
+    into: Option<bool>,
+    strip_option: Option<bool>,
+    try_into: Option<bool>,
+    name: Option<Ident>,
+    vis: Option<Visibility>,
+    skip: Option<Span>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pattern {
+    Mutable,
+    Owned,
+    Immutable,
+}
+
+impl Pattern {
+    // Wraps setter statements, which refer to the builder being modified as
+    // `builder`, in a method taking the receiver this pattern calls for. A
+    // setter given an error type returns its builder wrapped in a Result, and
+    // its statements may return early with that error.
+    fn setter(
+        self,
+        vis: &TokenStream,
+        name: &Ident,
+        generics: TokenStream,
+        params: TokenStream,
+        body: TokenStream,
+        error: Option<&TokenStream>,
+    ) -> TokenStream {
+        let ret = match self {
+            Pattern::Mutable => quote!(&mut Self),
+            Pattern::Owned | Pattern::Immutable => quote!(Self),
+        };
+        let (ret, builder) = setter_return(ret, quote!(builder), error);
+        match self {
+            Pattern::Mutable => quote! {
+                #vis fn #name #generics(&mut self, #params) -> #ret {
+                    let builder = self;
+                    #body
+                    #builder
+                }
+            },
+            Pattern::Owned => quote! {
+                #vis fn #name #generics(self, #params) -> #ret {
+                    let mut builder = self;
+                    #body
+                    #builder
+                }
+            },
+            Pattern::Immutable => quote! {
+                #vis fn #name #generics(&self, #params) -> #ret
+                where
+                    Self: core::clone::Clone,
+                {
+                    let mut builder = core::clone::Clone::clone(self);
+                    #body
+                    #builder
+                }
+            },
+        }
+    }
+
+    fn build_receiver(self) -> TokenStream {
+        match self {
+            Pattern::Mutable => quote!(&mut self),
+            Pattern::Owned => quote!(self),
+            Pattern::Immutable => quote!(&self),
+        }
+    }
+}
+
+// A field of the target as seen by the builder.
+struct BuilderField {
+    // Names both the builder's storage for the field and its setter.
+    ident: Ident,
+    // Names the field when constructing the target.
+    member: Member,
+    vis: Visibility,
+    // The field's doc comments, repeated on its setters.
+    docs: Vec<Attribute>,
+    ty: Type,
+    opts: FieldOpts,
+    // What an `each` field's collection is extended with.
+    collection: Option<CollectionItem>,
+    // The builder a `sub_builder` field is configured through.
+    sub_builder: Option<Type>,
+}
+
+impl BuilderField {
+    // The value the field takes when its setter was never called, or None if
+    // the field is required. Option and Vec fields default implicitly, as do
+    // all fields of a `#[builder(default)]` struct.
+    fn default_value(&self, struct_default: bool) -> Option<TokenStream> {
+        let is_optional = get_inner_ty(&self.ty, "Option").is_some();
+        let is_vec = get_inner_ty(&self.ty, "Vec").is_some();
+        match &self.opts.default {
+            Some(FieldDefault::Expr(expr, _)) => Some(quote!(#expr)),
+            Some(FieldDefault::Trait) => Some(quote!(core::default::Default::default())),
+            None if struct_default || is_optional || is_vec => {
+                Some(quote!(core::default::Default::default()))
+            }
+            None => None,
+        }
+    }
+
+    // The line appended to the field's docs on its setters, saying what
+    // happens when none of them is called.
+    fn default_doc(&self, struct_default: bool) -> String {
+        if self.sub_builder.is_some() {
+            return "This field is configured through its own builder.".to_owned();
+        }
+        if self.opts.each.is_some() {
+            return "This field starts out empty.".to_owned();
+        }
+        let is_optional = get_inner_ty(&self.ty, "Option").is_some();
+        match &self.opts.default {
+            Some(FieldDefault::Expr(_, expr)) => format!("If not set, defaults to `{}`.", expr),
+            None if is_optional => "This field is optional.".to_owned(),
+            Some(FieldDefault::Trait) | None if self.default_value(struct_default).is_some() => {
+                "If not set, defaults to `Default::default()`.".to_owned()
+            }
+            _ => "This field is required.".to_owned(),
+        }
+    }
+}
+
+struct FieldOpts {
+    name: Option<Ident>,
+    each: Option<Ident>,
+    default: Option<FieldDefault>,
+    sub_builder: Option<SubBuilder>,
+    // The method that builds a sub-builder, if it is not `build`.
+    sub_build_fn: Option<Ident>,
+    accessors: Option<bool>,
+    setter: SetterOpts,
+    // Forwarded onto the builder's storage for the field.
+    attrs: Vec<Meta>,
+}
+
+enum FieldDefault {
+    Trait,
+    // Keeps the expression as written, for the setter's documentation.
+    Expr(Expr, String),
+}
+
+enum SubBuilder {
+    // Named after the field's type, as the derive names builders by default.
+    Derived,
+    Type(Box<Type>),
+}
+
+// One buildable shape: either the struct the derive is applied to, or one
+// struct-like variant of an enum.
+struct BuilderTarget {
+    builder_name: Ident,
+    constructor: Ident,
+    path: TokenStream,
+    fields: Vec<BuilderField>,
+    opts: BuilderOpts,
+    // Set for the builder of an enum variant.
+    variant: bool,
+}
 
 #[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
-    let name = input.ident;
+    let name = &input.ident;
 
-    let mut fields = Vec::new();
-    match input.data {
-        Data::Struct(ds) => {
-            for f in ds.fields {
-                fields.push(f);
+    let mut targets = Vec::new();
+    let mut errors = Vec::new();
+    match &input.data {
+        Data::Struct(ds) => match (parse_builder_opts(&input.attrs), parse_fields(&ds.fields)) {
+            (Ok(opts), Ok(fields)) => targets.push(BuilderTarget {
+                builder_name: opts
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format_ident!("{}Builder", name)),
+                constructor: opts
+                    .constructor
+                    .clone()
+                    .unwrap_or_else(|| format_ident!("builder")),
+                path: quote!(#name),
+                fields,
+                opts,
+                variant: false,
+            }),
+            (opts, fields) => errors.extend(opts.err().into_iter().chain(fields.err())),
+        },
+        Data::Enum(de) => {
+            // Every variant gets its own builder, so names can only be chosen
+            // per variant.
+            if let Ok(opts) = parse_builder_opts(&input.attrs) {
+                for ident in opts.name.iter().chain(&opts.constructor) {
+                    errors.push(syn::Error::new(
+                        ident.span(),
+                        "builder names of an enum must be set on its variants",
+                    ));
+                }
+            }
+            for variant in &de.variants {
+                let variant_name = &variant.ident;
+                match &variant.fields {
+                    Fields::Named(named) => {
+                        let opts = parse_builder_opts(input.attrs.iter().chain(&variant.attrs));
+                        match (opts, parse_fields(&named.named)) {
+                            (Ok(opts), Ok(fields)) => targets.push(BuilderTarget {
+                                builder_name: opts.name.clone().unwrap_or_else(|| {
+                                    format_ident!("{}{}Builder", name, variant_name)
+                                }),
+                                constructor: opts.constructor.clone().unwrap_or_else(|| {
+                                    format_ident!(
+                                        "{}_builder",
+                                        to_snake_case(&variant_name.unraw().to_string())
+                                    )
+                                }),
+                                path: quote!(#name::#variant_name),
+                                fields,
+                                opts,
+                                variant: true,
+                            }),
+                            (opts, fields) => {
+                                errors.extend(opts.err().into_iter().chain(fields.err()))
+                            }
+                        }
+                    }
+                    Fields::Unnamed(_) => errors.push(syn::Error::new_spanned(
+                        variant,
+                        format!(
+                            "Builder requires named fields, `{}` is a tuple variant",
+                            variant_name
+                        ),
+                    )),
+                    Fields::Unit => errors.push(syn::Error::new_spanned(
+                        variant,
+                        format!(
+                            "Builder requires named fields, `{}` is a unit variant",
+                            variant_name
+                        ),
+                    )),
+                }
+            }
+        }
+        Data::Union(du) => errors.push(syn::Error::new_spanned(
+            du.union_token,
+            "Builder cannot be derived for unions",
+        )),
+    }
+
+    for target in &targets {
+        for f in &target.fields {
+            // `merge` is only generated on request, since a field may have a
+            // setter of that name.
+            let setter_name = f.opts.setter.name.as_ref().unwrap_or(&f.ident);
+            let setter_names = f.opts.setter.skip.is_none().then_some(setter_name);
+            for name in setter_names.into_iter().chain(&f.opts.each) {
+                if target.opts.merge && name == "merge" {
+                    errors.push(syn::Error::new(
+                        name.span(),
+                        "the `merge` setter conflicts with `#[builder(merge)]`",
+                    ));
+                }
+            }
+            // A field without a setter can only ever take its default.
+            if let Some(skip) = f.opts.setter.skip {
+                if f.opts.each.is_none()
+                    && f.sub_builder.is_none()
+                    && f.default_value(target.opts.default).is_none()
+                {
+                    errors.push(syn::Error::new(
+                        skip,
+                        "`setter(skip)` requires the field to have a default",
+                    ));
+                }
             }
         }
-        Data::Enum(_) => {}
-        Data::Union(_) => {}
     }
 
-    let builder_name = format_ident!("{}Builder", name);
+    if let Some(error) = combine_errors(errors) {
+        return error.to_compile_error().into();
+    }
+
+    let mut result = TokenStream::new();
+    for target in targets {
+        result.extend(expand_builder(name, &input.generics, target));
+    }
+    result.into()
+}
 
-    let field_name: Vec<_> = fields.iter().map(|f| &f.ident).collect();
+fn expand_builder(name: &Ident, generics: &Generics, target: BuilderTarget) -> TokenStream {
+    let BuilderTarget {
+        builder_name,
+        constructor,
+        path,
+        fields,
+        opts,
+        variant,
+    } = target;
 
-    let is_optional = |t: &Type| get_inner_ty(t, "Option").is_some();
+    let error_name = format_ident!("{}Error", builder_name);
+    // Items from core are always named through `core`, which every crate can
+    // use. The rest come from `alloc` when the target crate is no_std.
+    let (alloc, error_trait) = if opts.no_std {
+        (quote!(alloc), quote!(core::error::Error))
+    } else {
+        (quote!(std), quote!(std::error::Error))
+    };
+    let error_ty = match &opts.error {
+        Some(ty) => quote!(#ty),
+        None => quote!(#error_name),
+    };
 
-    let is_vec = |t: &Type| get_inner_ty(t, "Vec").is_some();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let field_conversion: Vec<TokenStream> = fields.iter().map(|f| {
-        let field_name = &f.ident.as_ref().unwrap();
-        if is_optional(&f.ty) || is_vec(&f.ty) {
-            quote! {
-                let #field_name = &self.#field_name;
+    let type_params: Vec<&Ident> = generics
+        .params
+        .iter()
+        .filter_map(|p| match p {
+            GenericParam::Type(t) => Some(&t.ident),
+            _ => None,
+        })
+        .collect();
+
+    let default_value = |f: &BuilderField| f.default_value(opts.default);
+
+    // Unless the builder is consumed, build() clones every field out of it,
+    // so each field type that involves a type parameter needs a Clone bound on
+    // that method only, plus a Default bound where the field falls back to
+    // Default::default().
+    let build_bound: Vec<TokenStream> = fields
+        .iter()
+        .filter(|f| f.sub_builder.is_none())
+        .filter(|f| type_params.iter().any(|p| ty_mentions_param(&f.ty, p)))
+        .filter_map(|f| {
+            let ty = &f.ty;
+            let uses_default_trait = f.opts.each.is_none()
+                && !matches!(f.opts.default, Some(FieldDefault::Expr(..)))
+                && default_value(f).is_some();
+            match (opts.pattern == Pattern::Owned, uses_default_trait) {
+                (true, false) => None,
+                (true, true) => Some(quote! {
+                    #ty: core::default::Default,
+                }),
+                (false, false) => Some(quote! {
+                    #ty: core::clone::Clone,
+                }),
+                (false, true) => Some(quote! {
+                    #ty: core::clone::Clone + core::default::Default,
+                }),
             }
-        } else {
-            quote! {
-                let std::option::Option::Some(#field_name) = &self.#field_name else { return std::result::Result::Err(std::boxed::Box::from(format!("field cannot be None: {}", stringify!(#field_name)).to_string()))};
+        })
+        .collect();
+
+    let field_name: Vec<&Ident> = fields.iter().map(|f| &f.ident).collect();
+    let field_member: Vec<&Member> = fields.iter().map(|f| &f.member).collect();
+
+    let required_field: Vec<&Ident> = fields
+        .iter()
+        .filter(|f| f.opts.each.is_none() && f.sub_builder.is_none() && default_value(f).is_none())
+        .map(|f| &f.ident)
+        .collect();
+
+    // In typestate mode every required field gets a type parameter on the
+    // builder recording whether its setter has been called, so that build()
+    // can only be called once all of them have been.
+    let state_mod = format_ident!("__{}Typestate", builder_name);
+    let state_param: Vec<Ident> = if opts.typestate {
+        required_field
+            .iter()
+            .map(|f| {
+                let camel = to_camel_case(&f.unraw().to_string());
+                format_ident!("__{}", camel, span = f.span())
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut builder_generics = generics.clone();
+    let mut builder_impl_generics = generics.clone();
+    for p in &state_param {
+        builder_generics
+            .params
+            .push(parse_quote!(#p = #state_mod::Unset));
+        builder_impl_generics.params.push(parse_quote!(#p));
+    }
+    let (builder_impl_generics, builder_ty_generics, _) = builder_impl_generics.split_for_impl();
+    let generic_arg: Vec<TokenStream> = generics
+        .params
+        .iter()
+        .map(|p| match p {
+            GenericParam::Lifetime(l) => {
+                let lifetime = &l.lifetime;
+                quote!(#lifetime)
             }
-        }
-    }).collect();
+            GenericParam::Type(t) => {
+                let ident = &t.ident;
+                quote!(#ident)
+            }
+            GenericParam::Const(c) => {
+                let ident = &c.ident;
+                quote!(#ident)
+            }
+        })
+        .collect();
+
+    let field_conversion: Vec<TokenStream> = fields
+        .iter()
+        .map(|f| {
+            let field_name = &f.ident;
+            let owned = opts.pattern == Pattern::Owned;
+            // A sub-builder is built in place, and its error is reported as
+            // an invalid value for the field holding it. An immutable builder
+            // cannot lend its sub-builders out mutably, so it builds a copy of
+            // each, like it does with every other field.
+            if f.sub_builder.is_some() {
+                let sub_builder = match opts.pattern {
+                    Pattern::Owned => quote! {
+                        #[allow(unused_mut)]
+                        let mut builder = self.#field_name;
+                    },
+                    Pattern::Mutable => quote! {
+                        let builder = &mut self.#field_name;
+                    },
+                    Pattern::Immutable => quote! {
+                        #[allow(unused_mut)]
+                        let mut builder = core::clone::Clone::clone(&self.#field_name);
+                    },
+                };
+                // Spanned on the field, so that an inner builder without a
+                // suitable build method is pointed out there.
+                let build_fn_name = f
+                    .opts
+                    .sub_build_fn
+                    .clone()
+                    .unwrap_or_else(|| format_ident!("build"));
+                let build = quote_spanned!(f.ty.span()=> builder.#build_fn_name());
+                return quote! {
+                    let #field_name = {
+                        #sub_builder
+                        match #build {
+                            core::result::Result::Ok(value) => value,
+                            core::result::Result::Err(e) => {
+                                return core::result::Result::Err(core::convert::From::from(#error_name::InvalidValue {
+                                    field: stringify!(#field_name),
+                                    message: #alloc::string::ToString::to_string(&e),
+                                }));
+                            }
+                        }
+                    };
+                };
+            }
+            if f.opts.each.is_some() {
+                return if owned {
+                    quote! {
+                        let #field_name = self.#field_name;
+                    }
+                } else {
+                    quote! {
+                        let #field_name = core::clone::Clone::clone(&self.#field_name);
+                    }
+                };
+            }
+            let fallback = match default_value(f) {
+                Some(default) => default,
+                None => quote!(unreachable!()),
+            };
+            if owned {
+                quote! {
+                    let #field_name = match self.#field_name {
+                        core::option::Option::Some(value) => value,
+                        core::option::Option::None => #fallback,
+                    };
+                }
+            } else {
+                quote! {
+                    let #field_name = match &self.#field_name {
+                        core::option::Option::Some(value) => core::clone::Clone::clone(value),
+                        core::option::Option::None => #fallback,
+                    };
+                }
+            }
+        })
+        .collect();
+
+    // A serde builder can be loaded from a partial configuration, and
+    // serializes without the fields that were never set.
+    let serde = opts.serde;
 
     let builder_field: Vec<TokenStream> = fields
         .iter()
         .map(|f| {
-            let field_name = &f.ident.as_ref().unwrap();
+            let field_name = &f.ident;
             let field_type = &f.ty;
-            if is_optional(&f.ty) || is_vec(&f.ty) {
+            let attrs = &f.opts.attrs;
+            if let Some(sub_builder) = &f.sub_builder {
+                let serde_attr = serde.then(|| quote!(#[serde(default)]));
                 quote! {
+                    #serde_attr
+                    #( #[#attrs] )*
+                    #field_name: #sub_builder,
+                }
+            } else if f.opts.each.is_some() {
+                let serde_attr = serde.then(|| quote!(#[serde(default)]));
+                quote! {
+                    #serde_attr
+                    #( #[#attrs] )*
                     #field_name: #field_type,
                 }
+            } else {
+                let serde_attr = serde.then(|| {
+                    quote! {
+                        #[serde(default, skip_serializing_if = "core::option::Option::is_none")]
+                    }
+                });
+                quote! {
+                    #serde_attr
+                    #( #[#attrs] )*
+                    #field_name: core::option::Option<#field_type>,
+                }
+            }
+        })
+        .collect();
+
+    // With `merge`, the builder can be layered over another, keeping every
+    // field set in either and preferring `other`. Collections keep the items
+    // of both, and sub-builders are merged in turn.
+    let field_merge: Vec<TokenStream> = fields
+        .iter()
+        .map(|f| {
+            let field_name = &f.ident;
+            if f.sub_builder.is_some() {
+                quote! {
+                    self.#field_name.merge(other.#field_name);
+                }
+            } else if f.opts.each.is_some() {
+                quote! {
+                    core::iter::Extend::extend(&mut self.#field_name, other.#field_name);
+                }
             } else {
                 quote! {
-                    #field_name: std::option::Option<#field_type>,
+                    if other.#field_name.is_some() {
+                        self.#field_name = other.#field_name;
+                    }
                 }
             }
         })
         .collect();
 
+    // Variant builders may not mention every generic parameter of the enum.
+    let (marker_field, marker_init) = if variant {
+        let lifetime = generics.lifetimes().map(|l| &l.lifetime);
+        let type_param = &type_params;
+        let serde_attr = serde.then(|| quote!(#[serde(skip)]));
+        (
+            quote! {
+                #serde_attr
+                __marker: core::marker::PhantomData<(#( &#lifetime (), )* #( #type_param, )*)>,
+            },
+            quote! {
+                __marker: core::marker::PhantomData,
+            },
+        )
+    } else {
+        (TokenStream::new(), TokenStream::new())
+    };
+
+    let vis = match &opts.vis {
+        Some(vis) => quote!(#vis),
+        None => quote!(pub),
+    };
+    // Setters are as visible as the field they set, unless overridden. Fields
+    // of enum variants have no visibility of their own and take the builder's.
+    let setter_vis =
+        |f: &BuilderField| match f.opts.setter.vis.as_ref().or(opts.setter.vis.as_ref()) {
+            Some(vis) => quote!(#vis),
+            None if variant => vis.clone(),
+            None => {
+                let vis = &f.vis;
+                quote!(#vis)
+            }
+        };
+
+    let storage_name = &field_name;
     let field_setter: Vec<TokenStream> = fields
         .iter()
         .map(|f| {
-            let field_name = &f.ident.as_ref().unwrap();
-            let opt_inner_ty = get_inner_ty(&f.ty, "Option");
-            let field_type = match opt_inner_ty {
-                Some(inner_ty) => inner_ty,
-                None => &f.ty,
+            if f.opts.setter.skip.is_some() {
+                return TokenStream::new();
+            }
+            let field_name = &f.ident;
+            let setter_name = f.opts.setter.name.as_ref().unwrap_or(field_name);
+            let setter_vis = setter_vis(f);
+            let field_docs = &f.docs;
+            let separator = (!f.docs.is_empty()).then(|| quote!(#[doc = ""]));
+            let default_doc = f.default_doc(opts.default);
+            let docs = quote! {
+                #( #field_docs )*
+                #separator
+                #[doc = #default_doc]
             };
-            let mut builder_attr_each = None;
-            for attr in &f.attrs {
-                if attr.path().is_ident("builder") {
-                    let mut value = None;
-                    if let Err(e) = attr.parse_nested_meta(|meta| {
-                        if meta.path.is_ident("each") {
-                            value = Some(meta.value()?.parse::<LitStr>()?);
-                            Ok(())
-                        } else {
-                            Err(meta.error("expected `builder(each = \"...\")`"))
-                        }
-                    }) {
-                        return e.to_compile_error();
+            if let Some(sub_builder) = &f.sub_builder {
+                return quote! {
+                    #docs
+                    #setter_vis fn #setter_name(&mut self) -> &mut #sub_builder {
+                        &mut self.#field_name
                     }
-                    let value = value.expect("Could not parse attribute value");
-                    let str = value.value();
-                    let ident: Ident = Ident::new(&str, value.span());
-                    builder_attr_each = Some(ident);
-                }
+                };
             }
-            let vec_inner_ty = get_inner_ty(&f.ty, "Vec");
-            let non_vec_setter = || {
+            let into = f.opts.setter.into.or(opts.setter.into).unwrap_or(false);
+            let strip_option = f
+                .opts
+                .setter
+                .strip_option
+                .or(opts.setter.strip_option)
+                .unwrap_or(true);
+            let try_into = f
+                .opts
+                .setter
+                .try_into
+                .or(opts.setter.try_into)
+                .unwrap_or(false);
+            let param = |ty: &Type| {
+                if into {
+                    quote!(it: impl core::convert::Into<#ty>)
+                } else {
+                    quote!(it: #ty)
+                }
+            };
+            let value = if into {
+                quote!(core::convert::Into::into(it))
+            } else {
+                quote!(it)
+            };
+            // Setting a typestate field moves the builder into a type where
+            // that field's state parameter is Set.
+            let state_index = required_field
+                .iter()
+                .position(|r| *r == field_name)
+                .filter(|_| opts.typestate);
+            let bare_setter = |name: &Ident,
+                               generics: TokenStream,
+                               params: TokenStream,
+                               body: TokenStream,
+                               error: Option<&TokenStream>| {
+                let Some(index) = state_index else {
+                    return opts.pattern.setter(&setter_vis, name, generics, params, body, error);
+                };
+                let state_arg = state_param.iter().enumerate().map(|(i, p)| {
+                    if i == index {
+                        quote!(#state_mod::Set)
+                    } else {
+                        quote!(#p)
+                    }
+                });
+                let (ret, builder) = setter_return(
+                    quote!(#builder_name<#( #generic_arg, )* #( #state_arg ),*>),
+                    quote! {
+                        #builder_name {
+                            #( #storage_name: builder.#storage_name, )*
+                            #marker_init
+                            __typestate: core::marker::PhantomData,
+                        }
+                    },
+                    error,
+                );
                 quote! {
-                    pub fn #field_name(&mut self, it: #field_type) -> &mut Self {
-                        self.#field_name = Some(it);
-                        self
+                    #setter_vis fn #name #generics(self, #params) -> #ret {
+                        let mut builder = self;
+                        #body
+                        #builder
                     }
                 }
             };
-            let vec_setter = || {
+            let wrap_setter = |name: &Ident,
+                               generics: TokenStream,
+                               params: TokenStream,
+                               body: TokenStream,
+                               error: Option<&TokenStream>| {
+                let setter = bare_setter(name, generics, params, body, error);
                 quote! {
-                    pub fn #field_name(&mut self, it: #field_type) -> &mut Self {
-                        self.#field_name = it;
-                        self
-                    }
+                    #docs
+                    #setter
                 }
             };
-            let default = |t: &Type| {
-                if is_vec(t) {
-                    vec_setter()
-                } else {
-                    non_vec_setter()
+            let opt_inner_ty = get_inner_ty(&f.ty, "Option").filter(|_| strip_option);
+            let setter_ty = opt_inner_ty.unwrap_or(&f.ty);
+            let store = |value: &TokenStream| match opt_inner_ty {
+                Some(_) => quote! {
+                    builder.#field_name = core::option::Option::Some(core::option::Option::Some(#value));
+                },
+                None => quote! {
+                    builder.#field_name = core::option::Option::Some(#value);
+                },
+            };
+            // With `setter(try_into)` the field also gets a `try_<field>` setter
+            // that returns the error of a failed conversion as it is.
+            let non_collection_setter = || {
+                let setter = wrap_setter(
+                    setter_name,
+                    TokenStream::new(),
+                    param(setter_ty),
+                    store(&value),
+                    None,
+                );
+                if !try_into {
+                    return setter;
+                }
+                let try_name = format_ident!("try_{}", setter_name.unraw());
+                let store = store(&quote!(value));
+                let try_setter = wrap_setter(
+                    &try_name,
+                    quote!(<__Value: core::convert::TryInto<#setter_ty>>),
+                    quote!(it: __Value),
+                    quote! {
+                        let value = match core::convert::TryInto::try_into(it) {
+                            core::result::Result::Ok(value) => value,
+                            core::result::Result::Err(e) => return core::result::Result::Err(e),
+                        };
+                        #store
+                    },
+                    Some(&quote!(<__Value as core::convert::TryInto<#setter_ty>>::Error)),
+                );
+                quote! {
+                    #setter
+                    #try_setter
                 }
             };
-            match builder_attr_each {
-                Some(builder_attr_each) => {
-                    if builder_attr_each != **field_name {
-                        let Some(field_type) = vec_inner_ty else {
-                            panic!("Can't use each attribute on type that isn't a vec")
+            // Collections are filled through Extend, one item at a time from
+            // the `each` setter or in bulk from `extend_<field>`. Maps take the
+            // key and value as separate arguments.
+            let collection_setter = |each: &Ident, item: &CollectionItem| {
+                let value_ty = &item.value;
+                let (each_setter, item_ty) = match &item.key {
+                    None => (
+                        wrap_setter(
+                            each,
+                            TokenStream::new(),
+                            param(value_ty),
+                            quote! {
+                                core::iter::Extend::extend(&mut builder.#field_name, core::iter::once(#value));
+                            },
+                            None,
+                        ),
+                        quote!(#value_ty),
+                    ),
+                    Some(key_ty) => {
+                        let (key_param, key) = if into {
+                            (
+                                quote!(key: impl core::convert::Into<#key_ty>),
+                                quote!(core::convert::Into::into(key)),
+                            )
+                        } else {
+                            (quote!(key: #key_ty), quote!(key))
                         };
-                        quote! {
-                            pub fn #builder_attr_each(&mut self, it: #field_type) -> &mut Self {
-                                self.#field_name.push(it);
-                                self
-                            }
+                        let (value_param, value) = if into {
+                            (
+                                quote!(value: impl core::convert::Into<#value_ty>),
+                                quote!(core::convert::Into::into(value)),
+                            )
+                        } else {
+                            (quote!(value: #value_ty), quote!(value))
+                        };
+                        (
+                            wrap_setter(
+                                each,
+                                TokenStream::new(),
+                                quote!(#key_param, #value_param),
+                                quote! {
+                                    core::iter::Extend::extend(&mut builder.#field_name, core::iter::once((#key, #value)));
+                                },
+                                None,
+                            ),
+                            quote!((#key_ty, #value_ty)),
+                        )
+                    }
+                };
+                let extend_name = format_ident!("extend_{}", setter_name.unraw());
+                let extend_setter = wrap_setter(
+                    &extend_name,
+                    TokenStream::new(),
+                    quote!(items: impl core::iter::IntoIterator<Item = #item_ty>),
+                    quote! {
+                        core::iter::Extend::extend(&mut builder.#field_name, items);
+                    },
+                    None,
+                );
+                quote! {
+                    #each_setter
+                    #extend_setter
+                }
+            };
+            match (&f.opts.each, &f.collection) {
+                (Some(each), Some(item)) => collection_setter(each, item),
+                _ => non_collection_setter(),
+            }
+        })
+        .collect();
+
+    // Read accessors and clear_<field>, generated for `accessors` fields only.
+    // Clearing a required field of a typestate builder would leave its state
+    // Set without a value, so those fields cannot be cleared.
+    let field_accessor: Vec<TokenStream> = fields
+        .iter()
+        .filter(|f| f.opts.accessors.unwrap_or(opts.accessors))
+        .map(|f| {
+            let field_name = &f.ident;
+            let field_type = &f.ty;
+            let vis = setter_vis(f);
+            let bare_name = field_name.unraw();
+            let has_name = format_ident!("has_{}", bare_name);
+            let get_name = format_ident!("get_{}", bare_name);
+            let clear_name = format_ident!("clear_{}", bare_name);
+            let has_doc = format!("Returns whether `{}` has been set.", bare_name);
+            let get_doc = format!("Returns the value of `{}`, if it has been set.", bare_name);
+            let clear = |value: TokenStream| {
+                if opts.typestate && required_field.contains(&field_name) {
+                    return TokenStream::new();
+                }
+                let clear_doc = format!("Resets `{}` to its initial state.", bare_name);
+                let clear = opts.pattern.setter(
+                    &vis,
+                    &clear_name,
+                    TokenStream::new(),
+                    TokenStream::new(),
+                    quote! {
+                        builder.#field_name = #value;
+                    },
+                    None,
+                );
+                quote! {
+                    #[doc = #clear_doc]
+                    #clear
+                }
+            };
+            if let Some(sub_builder) = &f.sub_builder {
+                let clear = clear(quote!(core::default::Default::default()));
+                let get_doc = format!("Returns the builder for `{}`.", bare_name);
+                quote! {
+                    #[doc = #get_doc]
+                    #vis fn #get_name(&self) -> &#sub_builder {
+                        &self.#field_name
+                    }
+
+                    #clear
+                }
+            } else if f.opts.each.is_some() {
+                let len_name = format_ident!("{}_len", bare_name);
+                let clear = clear(quote!(core::default::Default::default()));
+                let has_doc = format!("Returns whether `{}` has any items.", bare_name);
+                let get_doc = format!("Returns the items of `{}` added so far.", bare_name);
+                let len_doc = format!("Returns the number of items in `{}`.", bare_name);
+                quote! {
+                    #[doc = #has_doc]
+                    #vis fn #has_name(&self) -> bool {
+                        self.#field_name.len() != 0
+                    }
+
+                    #[doc = #get_doc]
+                    #vis fn #get_name(&self) -> &#field_type {
+                        &self.#field_name
+                    }
+
+                    #[doc = #len_doc]
+                    #vis fn #len_name(&self) -> usize {
+                        self.#field_name.len()
+                    }
+
+                    #clear
+                }
+            } else {
+                let clear = clear(quote!(core::option::Option::None));
+                // Like the setter, the getter of an Option field skips the
+                // inner Option unless `strip_option = false`.
+                let strip_option = f
+                    .opts
+                    .setter
+                    .strip_option
+                    .or(opts.setter.strip_option)
+                    .unwrap_or(true);
+                let get = match get_inner_ty(&f.ty, "Option").filter(|_| strip_option) {
+                    Some(inner_ty) => quote! {
+                        #vis fn #get_name(&self) -> core::option::Option<&#inner_ty> {
+                            self.#field_name.as_ref().and_then(core::option::Option::as_ref)
                         }
-                    } else {
-                        default(&f.ty)
+                    },
+                    None => quote! {
+                        #vis fn #get_name(&self) -> core::option::Option<&#field_type> {
+                            self.#field_name.as_ref()
+                        }
+                    },
+                };
+                quote! {
+                    #[doc = #has_doc]
+                    #vis fn #has_name(&self) -> bool {
+                        self.#field_name.is_some()
                     }
+
+                    #[doc = #get_doc]
+                    #get
+
+                    #clear
                 }
-                None => default(&f.ty),
             }
         })
         .collect();
 
+    // With `env_prefix`, apply_env() sets each field whose variable is present
+    // by parsing it with FromStr. Lists are split on the separator and their
+    // items parsed one by one; they replace the items added before, as other
+    // fields do. Sub-builders, maps and skipped fields are not loaded.
+    let env_loader = opts.env_prefix.as_ref().map(|prefix| {
+        let separator = opts.env_separator.as_deref().unwrap_or(",");
+        let mut parsed_ty = Vec::new();
+        let mut load_field = Vec::new();
+        for f in &fields {
+            if f.opts.setter.skip.is_some() || f.sub_builder.is_some() {
+                continue;
+            }
+            let field_name = &f.ident;
+            let var = format!("{}{}", prefix, field_name.unraw().to_string().to_uppercase());
+            let invalid = |e: TokenStream| {
+                quote! {
+                    return core::result::Result::Err(core::convert::From::from(#error_name::InvalidValue {
+                        field: stringify!(#field_name),
+                        message: std::format!("{}: {}", #var, #e),
+                    }))
+                }
+            };
+            let parse = |ty: &Type, value: TokenStream| {
+                let invalid = invalid(quote!(e));
+                quote! {
+                    match <#ty as core::str::FromStr>::from_str(#value) {
+                        core::result::Result::Ok(value) => value,
+                        core::result::Result::Err(e) => #invalid,
+                    }
+                }
+            };
+            let (ty, store) = match (&f.opts.each, &f.collection) {
+                (Some(_), Some(CollectionItem { key: Some(_), .. })) => continue,
+                (Some(_), Some(CollectionItem { value: item_ty, .. })) => {
+                    let item = parse(item_ty, quote!(item));
+                    let field_type = &f.ty;
+                    let store = quote! {
+                        let mut items: #field_type = core::default::Default::default();
+                        for item in value.split(#separator).filter(|item| !item.is_empty()) {
+                            let item = #item;
+                            core::iter::Extend::extend(&mut items, core::iter::once(item));
+                        }
+                        self.#field_name = items;
+                    };
+                    (item_ty, store)
+                }
+                _ => match (get_inner_ty(&f.ty, "Vec"), get_inner_ty(&f.ty, "Option")) {
+                    (Some(item_ty), _) => {
+                        let item = parse(item_ty, quote!(item));
+                        let store = quote! {
+                            let mut items = std::vec::Vec::new();
+                            for item in value.split(#separator).filter(|item| !item.is_empty()) {
+                                items.push(#item);
+                            }
+                            self.#field_name = core::option::Option::Some(items);
+                        };
+                        (item_ty, store)
+                    }
+                    (None, Some(inner_ty)) => {
+                        let value = parse(inner_ty, quote!(&value));
+                        let store = quote! {
+                            self.#field_name = core::option::Option::Some(core::option::Option::Some(#value));
+                        };
+                        (inner_ty, store)
+                    }
+                    (None, None) => {
+                        let value = parse(&f.ty, quote!(&value));
+                        let store = quote! {
+                            self.#field_name = core::option::Option::Some(#value);
+                        };
+                        (&f.ty, store)
+                    }
+                },
+            };
+            parsed_ty.push(ty);
+            let not_unicode = invalid(quote!(e));
+            load_field.push(quote! {
+                match std::env::var(#var) {
+                    core::result::Result::Ok(value) => {
+                        #store
+                    }
+                    core::result::Result::Err(std::env::VarError::NotPresent) => {}
+                    core::result::Result::Err(e) => #not_unicode,
+                }
+            });
+        }
+        // Spanned on the field type so that one which cannot be parsed is
+        // pointed out.
+        let parse_bound: Vec<TokenStream> = parsed_ty
+            .iter()
+            .map(|ty| {
+                quote_spanned! {ty.span()=>
+                    #ty: core::str::FromStr,
+                    <#ty as core::str::FromStr>::Err: core::fmt::Display,
+                }
+            })
+            .collect();
+        let from_env_doc = format!(
+            "Creates a [`{}`] with the fields set from `{}*` environment variables.",
+            builder_name, prefix
+        );
+        let apply_env_doc = format!(
+            "Sets each field whose `{}<FIELD>` environment variable is present, parsing it with [`FromStr`](core::str::FromStr).",
+            prefix
+        );
+        quote! {
+            #[doc = #from_env_doc]
+            #vis fn from_env() -> core::result::Result<Self, #error_ty>
+            where
+                #( #parse_bound )*
+            {
+                let mut builder = <#name #ty_generics>::#constructor();
+                builder.apply_env()?;
+                core::result::Result::Ok(builder)
+            }
+
+            #[doc = #apply_env_doc]
+            #vis fn apply_env(&mut self) -> core::result::Result<&mut Self, #error_ty>
+            where
+                #( #parse_bound )*
+            {
+                #( #load_field )*
+                core::result::Result::Ok(self)
+            }
+        }
+    });
+
+    let merge_fn = opts.merge.then(|| {
+        quote! {
+            /// Takes every field that is set in `other`, replacing the value
+            /// set here. Collections are extended and sub-builders merged.
+            pub fn merge(&mut self, other: Self) -> &mut Self {
+                #( #field_merge )*
+                self
+            }
+        }
+    });
+
     let builder_field_init: Vec<TokenStream> = fields
         .iter()
         .map(|f| {
-            let field_name = &f.ident.as_ref().unwrap();
-            if is_vec(&f.ty) {
+            let field_name = &f.ident;
+            if f.opts.each.is_some() || f.sub_builder.is_some() {
                 quote! {
-                    #field_name: vec![],
+                    #field_name: core::default::Default::default(),
                 }
             } else {
                 quote! {
-                    #field_name: None,
+                    #field_name: core::option::Option::None,
                 }
             }
         })
         .collect();
 
+    let build_receiver = opts.pattern.build_receiver();
+    let build_fn_name = opts
+        .build_fn_name
+        .clone()
+        .unwrap_or_else(|| format_ident!("build"));
+
+    // Spanned on the field so the unsatisfied bound points back at it.
+    let state_bound = state_param.iter().zip(&required_field).map(|(p, f)| {
+        let state_mod = Ident::new(&state_mod.to_string(), f.span());
+        quote_spanned! {f.span()=>
+            #p: #state_mod::#f,
+        }
+    });
+
+    // The validator sees the finished struct and may reject it with any error
+    // that the build error type can be created from.
+    let validate = opts.validate.as_ref().map(|validate| {
+        quote! {
+            if let core::result::Result::Err(e) = #validate(&value) {
+                return core::result::Result::Err(core::convert::From::from(e));
+            }
+        }
+    });
+
+    // With an async finisher, build() is an async fn which awaits it on the
+    // validated struct and returns whatever value it finishes with. Its error
+    // is converted into a custom build error type directly, and otherwise
+    // reported by message as a FinishError.
+    let (asyncness, finish) = match &opts.async_finish {
+        Some(finish) => {
+            let finish_error = if opts.error.is_some() {
+                quote!(e)
+            } else {
+                quote!(#error_name::FinishError(#alloc::string::ToString::to_string(&e)))
+            };
+            (
+                quote!(async),
+                quote! {
+                    let value = match #finish(value).await {
+                        core::result::Result::Ok(value) => value,
+                        core::result::Result::Err(e) => {
+                            return core::result::Result::Err(core::convert::From::from(#finish_error));
+                        }
+                    };
+                },
+            )
+        }
+        None => (TokenStream::new(), TokenStream::new()),
+    };
+
+    let infallible = validate.is_none()
+        && opts.async_finish.is_none()
+        && fields.iter().all(|f| f.sub_builder.is_none());
+    let build_fn = if opts.typestate && infallible {
+        quote! {
+            pub fn #build_fn_name(self) -> #name #ty_generics
+            where
+                #( #build_bound )*
+                #( #state_bound )*
+            {
+                #( #field_conversion )*
+                #path {
+                    #( #field_member: #field_name, )*
+                }
+            }
+        }
+    } else if opts.typestate {
+        quote! {
+            pub #asyncness fn #build_fn_name(self) -> core::result::Result<#name #ty_generics, #error_ty>
+            where
+                #( #build_bound )*
+                #( #state_bound )*
+            {
+                #( #field_conversion )*
+                let value = #path {
+                    #( #field_member: #field_name, )*
+                };
+                #validate
+                #finish
+                core::result::Result::Ok(value)
+            }
+        }
+    } else {
+        quote! {
+            pub #asyncness fn #build_fn_name(#build_receiver) -> core::result::Result<#name #ty_generics, #error_ty>
+            where
+                #( #build_bound )*
+            {
+                let mut missing_fields = #alloc::vec::Vec::new();
+                #(
+                    if self.#required_field.is_none() {
+                        missing_fields.push(stringify!(#required_field));
+                    }
+                )*
+                if !missing_fields.is_empty() {
+                    return core::result::Result::Err(core::convert::From::from(#error_name::MissingFields(missing_fields)));
+                }
+                #( #field_conversion )*
+                let value = #path {
+                    #( #field_member: #field_name, )*
+                };
+                #validate
+                #finish
+                core::result::Result::Ok(value)
+            }
+        }
+    };
+
+    let (typestate_mod, typestate_field, typestate_init) = if opts.typestate {
+        let unset_message = required_field.iter().map(|f| {
+            format!(
+                "`{}::{}()` requires field `{}` to be set",
+                builder_name,
+                build_fn_name,
+                f.unraw()
+            )
+        });
+        let unset_label = required_field
+            .iter()
+            .map(|f| format!("field `{}` has not been set", f.unraw()));
+        let unset_note = fields
+            .iter()
+            .filter(|f| required_field.contains(&&f.ident))
+            .map(|f| {
+                let setter_name = f.opts.setter.name.as_ref().unwrap_or(&f.ident);
+                format!(
+                    "call `.{}(...)` before `.{}()`",
+                    setter_name.unraw(),
+                    build_fn_name
+                )
+            });
+        (
+            quote! {
+                #[doc(hidden)]
+                #[allow(non_snake_case, non_camel_case_types)]
+                #vis mod #state_mod {
+                    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+                    pub struct Set;
+                    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+                    pub struct Unset;
+
+                    #(
+                        #[diagnostic::on_unimplemented(
+                            message = #unset_message,
+                            label = #unset_label,
+                            note = #unset_note,
+                        )]
+                        pub trait #required_field {}
+
+                        impl #required_field for Set {}
+                    )*
+                }
+            },
+            quote! {
+                __typestate: core::marker::PhantomData<(#( #state_param, )*)>,
+            },
+            quote! {
+                __typestate: core::marker::PhantomData,
+            },
+        )
+    } else {
+        (TokenStream::new(), TokenStream::new(), TokenStream::new())
+    };
+
+    // Immutable setters start from a copy of the builder they are called on.
+    let mut derives = opts.derives.clone();
+    if opts.pattern == Pattern::Immutable && !derives.iter().any(|d| d.is_ident("Clone")) {
+        derives.push(parse_quote!(Clone));
+    }
+    if serde {
+        derives.push(parse_quote!(serde::Serialize));
+        derives.push(parse_quote!(serde::Deserialize));
+    }
+    let builder_derive = if derives.is_empty() {
+        TokenStream::new()
+    } else {
+        quote!(#[derive(#( #derives ),*)])
+    };
+    let builder_attrs = &opts.attrs;
+
+    // A fresh builder is the default one, unless Default is derived instead.
+    let builder_default = if derives.iter().any(|d| d.is_ident("Default")) {
+        TokenStream::new()
+    } else {
+        quote! {
+            impl #impl_generics core::default::Default for #builder_name #ty_generics #where_clause {
+                fn default() -> Self {
+                    <#name #ty_generics>::#constructor()
+                }
+            }
+        }
+    };
+
+    // The builder every setter has been called on; the only one a typestate
+    // builder can be built from.
+    let complete_builder = if opts.typestate {
+        let set = state_param.iter().map(|_| quote!(#state_mod::Set));
+        quote!(#builder_name<#( #generic_arg, )* #( #set ),*>)
+    } else {
+        quote!(#builder_name #ty_generics)
+    };
+    let where_predicate = generics.where_clause.iter().flat_map(|w| &w.predicates);
+
+    let build_call = match opts.pattern {
+        Pattern::Mutable => quote! {
+            let mut builder = builder;
+            builder.#build_fn_name()
+        },
+        Pattern::Owned | Pattern::Immutable => quote! {
+            builder.#build_fn_name()
+        },
+    };
+    // An async build cannot back a conversion trait.
+    let from_builder = if opts.async_finish.is_some() {
+        TokenStream::new()
+    } else if opts.typestate && infallible {
+        quote! {
+            impl #impl_generics core::convert::From<#complete_builder> for #name #ty_generics
+            where
+                #( #where_predicate, )*
+                #( #build_bound )*
+            {
+                fn from(builder: #complete_builder) -> Self {
+                    #build_call
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics core::convert::TryFrom<#complete_builder> for #name #ty_generics
+            where
+                #( #where_predicate, )*
+                #( #build_bound )*
+            {
+                type Error = #error_ty;
+
+                fn try_from(builder: #complete_builder) -> core::result::Result<Self, Self::Error> {
+                    #build_call
+                }
+            }
+        }
+    };
+
+    // A variant builder cannot be created from every value of the enum, so
+    // only struct builders can start from an existing value.
+    //
+    // to_builder() needs the struct to be Clone, but the derive cannot tell
+    // whether it is. A plain `Self: Clone` bound on a struct that is not Clone
+    // is rejected where it is written, as bounds that can never hold are an
+    // error. Quantifying the bound over an unused lifetime makes the compiler
+    // check it only where to_builder() is called, so the method is simply
+    // unusable on such structs.
+    let to_builder = if variant {
+        TokenStream::new()
+    } else {
+        let to_builder_doc = format!(
+            "Creates a [`{}`] with every field set from `self`.",
+            builder_name
+        );
+        let field_storage = fields.iter().map(|f| {
+            let member = &f.member;
+            if f.sub_builder.is_some() {
+                quote!(core::convert::From::from(value.#member))
+            } else if f.opts.each.is_some() {
+                quote!(value.#member)
+            } else {
+                quote!(core::option::Option::Some(value.#member))
+            }
+        });
+        quote! {
+            impl #impl_generics core::convert::From<#name #ty_generics> for #complete_builder #where_clause {
+                fn from(value: #name #ty_generics) -> Self {
+                    #builder_name {
+                        #( #field_name: #field_storage, )*
+                        #typestate_init
+                    }
+                }
+            }
+
+            impl #impl_generics #name #ty_generics #where_clause {
+                #[doc = #to_builder_doc]
+                #vis fn to_builder(&self) -> #complete_builder
+                where
+                    // Checked at the call site only; see above.
+                    for<'__builder> Self: core::clone::Clone,
+                {
+                    core::convert::From::from(core::clone::Clone::clone(self))
+                }
+            }
+        }
+    };
+
+    // Every public item gets docs, so that the derive can be used in crates
+    // that deny missing_docs.
+    let target = path.to_string().replace(' ', "");
+    let builder_doc = format!("Builder for [`{}`].", target);
+    let error_doc = format!("Error returned when [`{}`] fails to build.", builder_name);
+    let constructor_doc = format!("Creates a [`{}`] with no fields set.", builder_name);
+    let build_doc = format!("Builds the [`{}`].", target);
+
     let result = quote! {
-        pub struct #builder_name {
+        #[doc = #error_doc]
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #vis enum #error_name {
+            /// Required fields whose setters were never called.
+            MissingFields(#alloc::vec::Vec<&'static str>),
+            /// The built value was rejected by its validator.
+            ValidationError(#alloc::string::String),
+            /// The value of a field could not be built or parsed.
+            InvalidValue {
+                /// The field whose value was rejected.
+                field: &'static str,
+                /// Why it was rejected.
+                message: #alloc::string::String,
+            },
+            /// The async finisher of the built value failed.
+            FinishError(#alloc::string::String),
+        }
+
+        impl #error_name {
+            /// Returns the missing fields, if that is why the build failed.
+            pub fn missing_fields(&self) -> &[&'static str] {
+                match self {
+                    #error_name::MissingFields(fields) => fields,
+                    _ => &[],
+                }
+            }
+        }
+
+        impl core::fmt::Display for #error_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                match self {
+                    #error_name::MissingFields(fields) => {
+                        f.write_str("missing required fields: ")?;
+                        for (i, field) in fields.iter().enumerate() {
+                            if i > 0 {
+                                f.write_str(", ")?;
+                            }
+                            f.write_str(field)?;
+                        }
+                        core::result::Result::Ok(())
+                    }
+                    #error_name::ValidationError(message) => write!(f, "validation failed: {}", message),
+                    #error_name::InvalidValue { field, message } => {
+                        write!(f, "invalid value for field `{}`: {}", field, message)
+                    }
+                    #error_name::FinishError(message) => write!(f, "finishing failed: {}", message),
+                }
+            }
+        }
+
+        impl #error_trait for #error_name {}
+
+        impl core::convert::From<#alloc::string::String> for #error_name {
+            fn from(message: #alloc::string::String) -> Self {
+                #error_name::ValidationError(message)
+            }
+        }
+
+        #typestate_mod
+
+        #[doc = #builder_doc]
+        #builder_derive
+        #( #[#builder_attrs] )*
+        #vis struct #builder_name #builder_generics #where_clause {
             #( #builder_field )*
+            #marker_field
+            #typestate_field
         }
 
-        impl #builder_name {
+        impl #builder_impl_generics #builder_name #builder_ty_generics #where_clause {
             #( #field_setter )*
 
-            pub fn build(&mut self) -> std::result::Result<Command, std::boxed::Box<dyn std::error::Error>> {
-                #( #field_conversion )*
-                std::result::Result::Ok(Command {
-                    #( #field_name : #field_name.clone(), )*
-                })
-            }
+            #( #field_accessor )*
+
+            #env_loader
+
+            #merge_fn
+
+            #[doc = #build_doc]
+            #build_fn
         }
 
-        impl #name {
-            pub fn builder() -> #builder_name {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc = #constructor_doc]
+            #vis fn #constructor() -> #builder_name #ty_generics {
                 #builder_name {
                     #( #builder_field_init )*
+                    #marker_init
+                    #typestate_init
                 }
             }
         }
+
+        #builder_default
+
+        #from_builder
+
+        #to_builder
     };
-    result.into()
+    result
+}
+
+fn parse_builder_opts<'a>(
+    attrs: impl IntoIterator<Item = &'a Attribute>,
+) -> syn::Result<BuilderOpts> {
+    let mut opts = BuilderOpts {
+        name: None,
+        vis: None,
+        constructor: None,
+        build_fn_name: None,
+        error: None,
+        validate: None,
+        async_finish: None,
+        default: false,
+        pattern: Pattern::Mutable,
+        setter: SetterOpts::default(),
+        typestate: false,
+        serde: false,
+        no_std: false,
+        accessors: false,
+        merge: false,
+        env_prefix: None,
+        env_separator: None,
+        derives: Vec::new(),
+        attrs: Vec::new(),
+    };
+    let mut pattern = None;
+    let mut serde = None;
+    let mut env = None;
+    let mut env_separator = None;
+    for attr in attrs {
+        if attr.path().is_ident("builder") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    opts.name = Some(value.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("vis") {
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    opts.vis = Some(value.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("constructor") {
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    opts.constructor = Some(value.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    opts.default = true;
+                    Ok(())
+                } else if meta.path.is_ident("derive") {
+                    meta.parse_nested_meta(|meta| {
+                        opts.derives.push(meta.path);
+                        Ok(())
+                    })
+                } else if meta.path.is_ident("struct_attr") {
+                    // Derives are merged with `derive(...)`, so that the checks
+                    // on the derived traits see them wherever they are listed.
+                    for attr in parse_forwarded_attrs(&meta)? {
+                        match attr {
+                            Meta::List(list) if list.path.is_ident("derive") => {
+                                opts.derives.extend(list.parse_args_with(
+                                    Punctuated::<Path, syn::Token![,]>::parse_terminated,
+                                )?);
+                            }
+                            attr => opts.attrs.push(attr),
+                        }
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("typestate") {
+                    opts.typestate = true;
+                    Ok(())
+                } else if meta.path.is_ident("no_std") {
+                    opts.no_std = true;
+                    Ok(())
+                } else if meta.path.is_ident("accessors") {
+                    opts.accessors = true;
+                    Ok(())
+                } else if meta.path.is_ident("merge") {
+                    opts.merge = true;
+                    Ok(())
+                } else if meta.path.is_ident("serde") {
+                    opts.serde = true;
+                    serde = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("env_prefix") {
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    opts.env_prefix = Some(value.value());
+                    env = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("env_separator") {
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    if value.value().is_empty() {
+                        return Err(syn::Error::new(value.span(), "the separator cannot be empty"));
+                    }
+                    opts.env_separator = Some(value.value());
+                    env_separator = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("pattern") {
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    opts.pattern = match value.value().as_str() {
+                        "mutable" => Pattern::Mutable,
+                        "owned" => Pattern::Owned,
+                        "immutable" => Pattern::Immutable,
+                        _ => {
+                            return Err(syn::Error::new(
+                                value.span(),
+                                "expected `mutable`, `owned` or `immutable`",
+                            ))
+                        }
+                    };
+                    pattern = Some(value);
+                    Ok(())
+                } else if meta.path.is_ident("setter") {
+                    meta.parse_nested_meta(|meta| parse_setter_opt(&meta, &mut opts.setter, false))
+                } else if meta.path.is_ident("build_fn") {
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("name") {
+                            let value = meta.value()?.parse::<LitStr>()?;
+                            opts.build_fn_name = Some(value.parse()?);
+                            Ok(())
+                        } else if meta.path.is_ident("error") {
+                            let value = meta.value()?.parse::<LitStr>()?;
+                            opts.error = Some(value.parse()?);
+                            Ok(())
+                        } else if meta.path.is_ident("validate") {
+                            let value = meta.value()?.parse::<LitStr>()?;
+                            opts.validate = Some(value.parse()?);
+                            Ok(())
+                        } else if meta.path.is_ident("async_finish") {
+                            let value = meta.value()?.parse::<LitStr>()?;
+                            opts.async_finish = Some(value.parse()?);
+                            Ok(())
+                        } else {
+                            Err(meta.error(
+                                "expected `build_fn(name = \"...\")`, `build_fn(error = \"...\")`, `build_fn(validate = \"...\")` or `build_fn(async_finish = \"...\")`",
+                            ))
+                        }
+                    })
+                } else {
+                    Err(meta.error("unrecognized builder option"))
+                }
+            })?;
+        }
+    }
+    if let Some(separator) = env_separator.filter(|_| env.is_none()) {
+        return Err(syn::Error::new(
+            separator,
+            "`env_separator` requires `env_prefix`",
+        ));
+    }
+    if let Some(env) = env.filter(|_| opts.no_std) {
+        return Err(syn::Error::new(
+            env,
+            "no_std builders cannot read environment variables",
+        ));
+    }
+    if opts.typestate {
+        // Every setter changes the builder's type, so it has to be consumed.
+        if let Some(pattern) = pattern.filter(|_| opts.pattern != Pattern::Owned) {
+            return Err(syn::Error::new(
+                pattern.span(),
+                "typestate builders always use the owned pattern",
+            ));
+        }
+        opts.pattern = Pattern::Owned;
+        // Deserializing could not update the state of the fields it sets.
+        if let Some(serde) = serde {
+            return Err(syn::Error::new(
+                serde,
+                "typestate builders cannot be deserialized",
+            ));
+        }
+        // Loading a field could not update its state either.
+        if let Some(env) = env {
+            return Err(syn::Error::new(
+                env,
+                "typestate builders cannot be loaded from the environment",
+            ));
+        }
+        // A derived impl would exist for builders whose fields are marked Set.
+        if let Some(default) = opts.derives.iter().find(|d| d.is_ident("Default")) {
+            return Err(syn::Error::new_spanned(
+                default,
+                "typestate builders cannot derive Default",
+            ));
+        }
+    }
+    // The future returned by an async build() would otherwise borrow the
+    // builder it was called on.
+    if let Some(finish) = opts
+        .async_finish
+        .as_ref()
+        .filter(|_| opts.pattern != Pattern::Owned)
+    {
+        return Err(syn::Error::new_spanned(
+            finish,
+            "`async_finish` requires `pattern = \"owned\"` or `typestate`",
+        ));
+    }
+    Ok(opts)
+}
+
+// Parses the options of every field, reporting the problems with all of them
+// together rather than stopping at the first.
+fn parse_fields<'a>(fields: impl IntoIterator<Item = &'a Field>) -> syn::Result<Vec<BuilderField>> {
+    let mut parsed = Vec::new();
+    let mut errors = Vec::new();
+    for (i, f) in fields.into_iter().enumerate() {
+        let opts = match parse_field_opts(f) {
+            Ok(opts) => opts,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        let (ident, member) = match &f.ident {
+            Some(ident) => {
+                if let Some(name) = &opts.name {
+                    errors.push(syn::Error::new(
+                        name.span(),
+                        "`name` only applies to tuple fields",
+                    ));
+                    continue;
+                }
+                (ident.clone(), Member::Named(ident.clone()))
+            }
+            None => (
+                opts.name
+                    .clone()
+                    .unwrap_or_else(|| format_ident!("field{}", i)),
+                Member::Unnamed(Index::from(i)),
+            ),
+        };
+        let collection = match &opts.each {
+            Some(_) => match collection_item(&f.ty) {
+                Some(item) => Some(item),
+                None => {
+                    errors.push(syn::Error::new_spanned(
+                        &f.ty,
+                        "`each` requires a collection type such as Vec<T> or HashMap<K, V>",
+                    ));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let sub_builder = match &opts.sub_builder {
+            Some(SubBuilder::Type(ty)) => Some(Type::clone(ty)),
+            Some(SubBuilder::Derived) => match sub_builder_ty(&f.ty) {
+                Some(ty) => Some(ty),
+                None => {
+                    errors.push(syn::Error::new_spanned(
+                        &f.ty,
+                        "`sub_builder` requires the type of a struct that derives Builder",
+                    ));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        parsed.push(BuilderField {
+            ident,
+            member,
+            vis: f.vis.clone(),
+            docs: f
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("doc"))
+                .cloned()
+                .collect(),
+            ty: f.ty.clone(),
+            opts,
+            collection,
+            sub_builder,
+        });
+    }
+    match combine_errors(errors) {
+        Some(error) => Err(error),
+        None => Ok(parsed),
+    }
+}
+
+fn combine_errors(errors: Vec<syn::Error>) -> Option<syn::Error> {
+    errors.into_iter().reduce(|mut all, e| {
+        all.combine(e);
+        all
+    })
+}
+
+fn parse_field_opts(f: &Field) -> syn::Result<FieldOpts> {
+    let mut opts = FieldOpts {
+        name: None,
+        each: None,
+        default: None,
+        sub_builder: None,
+        sub_build_fn: None,
+        accessors: None,
+        setter: SetterOpts::default(),
+        attrs: Vec::new(),
+    };
+    for attr in &f.attrs {
+        if attr.path().is_ident("builder") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    opts.name = Some(value.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("each") {
+                    if opts.default.is_some() {
+                        return Err(meta.error("`each` cannot be combined with `default`"));
+                    }
+                    if opts.sub_builder.is_some() {
+                        return Err(meta.error("`each` cannot be combined with `sub_builder`"));
+                    }
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    opts.each = Some(value.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    if opts.each.is_some() {
+                        return Err(meta.error("`default` cannot be combined with `each`"));
+                    }
+                    if opts.sub_builder.is_some() {
+                        return Err(meta.error("`default` cannot be combined with `sub_builder`"));
+                    }
+                    if meta.input.peek(syn::Token![=]) {
+                        let value = meta.value()?.parse::<LitStr>()?;
+                        opts.default = Some(FieldDefault::Expr(value.parse()?, value.value()));
+                    } else {
+                        opts.default = Some(FieldDefault::Trait);
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("sub_builder") {
+                    if opts.each.is_some() {
+                        return Err(meta.error("`sub_builder` cannot be combined with `each`"));
+                    }
+                    if opts.default.is_some() {
+                        return Err(meta.error("`sub_builder` cannot be combined with `default`"));
+                    }
+                    opts.sub_builder = Some(SubBuilder::Derived);
+                    if meta.input.peek(syn::Token![=]) {
+                        let value = meta.value()?.parse::<LitStr>()?;
+                        opts.sub_builder = Some(SubBuilder::Type(Box::new(value.parse()?)));
+                    } else if meta.input.peek(syn::token::Paren) {
+                        meta.parse_nested_meta(|meta| {
+                            if meta.path.is_ident("builder") {
+                                let value = meta.value()?.parse::<LitStr>()?;
+                                opts.sub_builder =
+                                    Some(SubBuilder::Type(Box::new(value.parse()?)));
+                                Ok(())
+                            } else if meta.path.is_ident("build_fn") {
+                                let value = meta.value()?.parse::<LitStr>()?;
+                                opts.sub_build_fn = Some(value.parse()?);
+                                Ok(())
+                            } else {
+                                Err(meta.error(
+                                    "expected `sub_builder(builder = \"...\")` or `sub_builder(build_fn = \"...\")`",
+                                ))
+                            }
+                        })?;
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("accessors") {
+                    opts.accessors = Some(parse_flag(&meta)?);
+                    Ok(())
+                } else if meta.path.is_ident("setter") {
+                    meta.parse_nested_meta(|meta| parse_setter_opt(&meta, &mut opts.setter, true))
+                } else if meta.path.is_ident("field_attr") {
+                    opts.attrs.extend(parse_forwarded_attrs(&meta)?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `builder(each = \"...\")`"))
+                }
+            })?;
+        }
+    }
+    Ok(opts)
+}
+
+// Parses the attributes in `struct_attr(...)` or `field_attr(...)`, which are
+// copied as they are onto the builder.
+fn parse_forwarded_attrs(meta: &ParseNestedMeta) -> syn::Result<Vec<Meta>> {
+    let content;
+    syn::parenthesized!(content in meta.input);
+    let attrs = Punctuated::<Meta, syn::Token![,]>::parse_terminated(&content)?;
+    Ok(attrs.into_iter().collect())
+}
+
+// The return type and final expression of a setter, wrapped in a Result when
+// the setter can fail with `error`.
+fn setter_return(
+    ret: TokenStream,
+    builder: TokenStream,
+    error: Option<&TokenStream>,
+) -> (TokenStream, TokenStream) {
+    match error {
+        Some(error) => (
+            quote!(core::result::Result<#ret, #error>),
+            quote!(core::result::Result::Ok(#builder)),
+        ),
+        None => (ret, builder),
+    }
+}
+
+fn parse_setter_opt(meta: &ParseNestedMeta, opts: &mut SetterOpts, field: bool) -> syn::Result<()> {
+    if !field && (meta.path.is_ident("name") || meta.path.is_ident("skip")) {
+        return Err(meta.error("only fields can rename or skip their setter"));
+    }
+    if meta.path.is_ident("into") {
+        opts.into = Some(parse_flag(meta)?);
+        Ok(())
+    } else if meta.path.is_ident("strip_option") {
+        opts.strip_option = Some(parse_flag(meta)?);
+        Ok(())
+    } else if meta.path.is_ident("try_into") {
+        opts.try_into = Some(parse_flag(meta)?);
+        Ok(())
+    } else if meta.path.is_ident("name") {
+        let value = meta.value()?.parse::<LitStr>()?;
+        opts.name = Some(value.parse()?);
+        Ok(())
+    } else if meta.path.is_ident("vis") {
+        let value = meta.value()?.parse::<LitStr>()?;
+        opts.vis = Some(value.parse()?);
+        Ok(())
+    } else if meta.path.is_ident("skip") {
+        opts.skip = parse_flag(meta)?.then(|| meta.path.span());
+        Ok(())
+    } else {
+        Err(meta.error("unrecognized setter option"))
+    }
+}
+
+// Accepts both `flag` and `flag = false`.
+fn parse_flag(meta: &ParseNestedMeta) -> syn::Result<bool> {
+    if meta.input.peek(syn::Token![=]) {
+        Ok(meta.value()?.parse::<LitBool>()?.value)
+    } else {
+        Ok(true)
+    }
+}
+
+fn to_snake_case(s: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn to_camel_case(s: &str) -> String {
+    s.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// Sequences and sets are extended with a plain value, maps with a key and a
+// value.
+struct CollectionItem {
+    key: Option<Type>,
+    value: Type,
+}
+
+// Guesses what a collection is extended with from its type arguments: the
+// first one for sequences and sets, a (key, value) pair for anything whose
+// name ends in Map. Any further arguments, like a hasher, are ignored.
+fn collection_item(t: &Type) -> Option<CollectionItem> {
+    let Type::Path(type_path) = t else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    let mut type_args = args.args.iter().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    let first = type_args.next()?;
+    if segment.ident.to_string().ends_with("Map") {
+        let second = type_args.next()?;
+        Some(CollectionItem {
+            key: Some(first.clone()),
+            value: second.clone(),
+        })
+    } else {
+        Some(CollectionItem {
+            key: None,
+            value: first.clone(),
+        })
+    }
+}
+
+// `TlsConfig<T>` is configured through `TlsConfigBuilder<T>`.
+fn sub_builder_ty(t: &Type) -> Option<Type> {
+    let Type::Path(type_path) = t else {
+        return None;
+    };
+    let mut type_path = type_path.clone();
+    let segment = type_path.path.segments.last_mut()?;
+    segment.ident = format_ident!("{}Builder", segment.ident);
+    Some(Type::Path(type_path))
 }
 
 fn get_inner_ty<'a>(t: &'a Type, expected_ident: &str) -> Option<&'a Type> {
@@ -191,3 +2033,38 @@ fn get_inner_ty<'a>(t: &'a Type, expected_ident: &str) -> Option<&'a Type> {
         _ => None,
     }
 }
+
+fn ty_mentions_param(t: &Type, param: &Ident) -> bool {
+    match t {
+        Type::Path(type_path) => {
+            if let Some(qself) = &type_path.qself {
+                if ty_mentions_param(&qself.ty, param) {
+                    return true;
+                }
+            }
+            if type_path.qself.is_none() && type_path.path.segments[0].ident == *param {
+                return true;
+            }
+            type_path.path.segments.iter().any(|segment| match &segment.arguments {
+                PathArguments::AngleBracketed(args) => args.args.iter().any(|arg| match arg {
+                    GenericArgument::Type(ty) => ty_mentions_param(ty, param),
+                    GenericArgument::AssocType(assoc) => ty_mentions_param(&assoc.ty, param),
+                    _ => false,
+                }),
+                PathArguments::Parenthesized(args) => {
+                    args.inputs.iter().any(|ty| ty_mentions_param(ty, param))
+                        || matches!(&args.output, ReturnType::Type(_, ty) if ty_mentions_param(ty, param))
+                }
+                PathArguments::None => false,
+            })
+        }
+        Type::Reference(r) => ty_mentions_param(&r.elem, param),
+        Type::Ptr(p) => ty_mentions_param(&p.elem, param),
+        Type::Slice(s) => ty_mentions_param(&s.elem, param),
+        Type::Array(a) => ty_mentions_param(&a.elem, param),
+        Type::Paren(p) => ty_mentions_param(&p.elem, param),
+        Type::Group(g) => ty_mentions_param(&g.elem, param),
+        Type::Tuple(t) => t.elems.iter().any(|ty| ty_mentions_param(ty, param)),
+        _ => false,
+    }
+}
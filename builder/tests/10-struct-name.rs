@@ -0,0 +1,68 @@
+// The builder should work for any struct, not only one called Command. The
+// generated `build` method needs to return and construct whatever type the
+// derive was applied to.
+//
+//     impl RequestBuilder {
+//         pub fn build(&mut self) -> Result<Request, Box<dyn Error>> {
+//             ...
+//         }
+//     }
+//
+// Several builders in the same module must not collide with one another, and
+// the macro should be equally happy with structs declared inside nested
+// modules.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Request {
+    method: String,
+    url: String,
+    body: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct Response {
+    status: u16,
+    #[builder(each = "header")]
+    headers: Vec<String>,
+}
+
+mod net {
+    pub mod tcp {
+        use derive_builder::Builder;
+
+        #[derive(Builder)]
+        pub struct Socket {
+            pub host: String,
+            pub port: u16,
+        }
+    }
+}
+
+fn main() {
+    let request = Request::builder()
+        .method("GET".to_owned())
+        .url("/index.html".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(request.method, "GET");
+    assert_eq!(request.url, "/index.html");
+    assert!(request.body.is_none());
+
+    let response = Response::builder()
+        .status(200)
+        .header("Content-Type: text/html".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.headers, vec!["Content-Type: text/html"]);
+
+    let socket = net::tcp::Socket::builder()
+        .host("localhost".to_owned())
+        .port(8080)
+        .build()
+        .unwrap();
+    assert_eq!(socket.host, "localhost");
+    assert_eq!(socket.port, 8080);
+}
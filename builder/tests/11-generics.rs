@@ -0,0 +1,72 @@
+// Generic structs need a generic builder. The builder struct, its impl block
+// and the `builder` constructor should all carry the lifetimes, type and const
+// parameters of the original struct, together with its where-clause.
+//
+//     pub struct ConfigBuilder<'a, T, const N: usize>
+//     where
+//         T: Default,
+//     {
+//         ...
+//     }
+//
+// The `build` method clones each field out of the builder, but that should not
+// force every type parameter to be Clone. Put the Clone bounds on `build` alone
+// so that a builder for a non-Clone type can still be created and filled in.
+//
+//
+// Resources:
+//
+//   - Splitting generics for use in an impl block:
+//     https://docs.rs/syn/2.0/syn/struct.Generics.html#method.split_for_impl
+
+use derive_builder::Builder;
+use std::fmt::Debug;
+
+#[derive(Builder)]
+pub struct Config<'a, T, const N: usize>
+where
+    T: Default,
+{
+    name: &'a str,
+    value: T,
+    tags: [u8; N],
+    #[builder(each = "extra")]
+    extras: Vec<T>,
+    fallback: Option<T>,
+}
+
+#[derive(Builder)]
+pub struct Bounded<T: Debug + Clone, U = String> {
+    item: T,
+    label: U,
+}
+
+#[derive(Default)]
+pub struct NotClone;
+
+fn main() {
+    let config = Config::builder()
+        .name("limits")
+        .value(5u32)
+        .tags([1, 2, 3])
+        .extra(6)
+        .extra(7)
+        .build()
+        .unwrap();
+    assert_eq!(config.name, "limits");
+    assert_eq!(config.value, 5);
+    assert_eq!(config.tags, [1, 2, 3]);
+    assert_eq!(config.extras, vec![6, 7]);
+    assert_eq!(config.fallback, None);
+
+    let mut builder = Config::<NotClone, 0>::builder();
+    builder.name("unclonable").value(NotClone).tags([]);
+
+    let bounded: Bounded<i32> = Bounded::builder()
+        .item(1)
+        .label("one".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(bounded.item, 1);
+    assert_eq!(bounded.label, "one");
+}
@@ -0,0 +1,60 @@
+// Rather than a boxed error built from a string, have the macro generate a
+// dedicated error enum next to the builder so that callers can match on what
+// went wrong.
+//
+//     #[derive(Debug, Clone, PartialEq, Eq)]
+//     pub enum CommandBuilderError {
+//         MissingFields(Vec<&'static str>),
+//         ValidationError(String),
+//     }
+//
+// The enum implements Display and std::error::Error so it still composes with
+// `?` and Box<dyn Error>.
+//
+// Callers who already have an error type of their own can ask for `build` to
+// return it instead through `#[builder(build_fn(error = "..."))]`. The generated
+// error is converted using the caller's From impl.
+
+use derive_builder::Builder;
+use std::error::Error;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AppError {
+    Config(String),
+}
+
+impl From<RequestBuilderError> for AppError {
+    fn from(err: RequestBuilderError) -> Self {
+        AppError::Config(err.to_string())
+    }
+}
+
+#[derive(Builder)]
+#[builder(build_fn(error = "AppError"))]
+pub struct Request {
+    url: String,
+}
+
+fn main() {
+    let err = Command::builder().build().err().unwrap();
+    assert_eq!(err, CommandBuilderError::MissingFields(vec!["executable"]));
+    assert_eq!(err.to_string(), "missing required fields: executable");
+
+    let boxed: Box<dyn Error> = Box::new(err);
+    assert!(boxed.source().is_none());
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+
+    let err = Request::builder().build().err().unwrap();
+    assert_eq!(err, AppError::Config("missing required fields: url".to_owned()));
+}
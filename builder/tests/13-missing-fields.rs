@@ -0,0 +1,51 @@
+// Stopping at the first missing field means the caller has to rebuild once per
+// forgotten field before finding out about the next one. Have `build` check
+// every required field and report all of the missing ones together, in the
+// order they were declared.
+//
+// The list of missing names should be available programmatically, not just in
+// the Display output.
+//
+//     impl CommandBuilderError {
+//         pub fn missing_fields(&self) -> &[&'static str] {
+//             ...
+//         }
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    timeout: u64,
+    current_dir: Option<String>,
+    user: String,
+}
+
+fn main() {
+    let err = Command::builder().build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["executable", "timeout", "user"]);
+    assert_eq!(
+        err.to_string(),
+        "missing required fields: executable, timeout, user",
+    );
+
+    let err = Command::builder()
+        .timeout(30)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err.missing_fields(), ["executable", "user"]);
+
+    let err = CommandBuilderError::ValidationError("bad".to_owned());
+    assert!(err.missing_fields().is_empty());
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .timeout(30)
+        .user("root".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.timeout, 30);
+}
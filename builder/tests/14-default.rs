@@ -0,0 +1,67 @@
+// Not every required field needs to be provided by the caller. Support a
+// `#[builder(default)]` field attribute which falls back to Default::default()
+// when the setter was never called, and `#[builder(default = "...")]` which
+// evaluates an arbitrary expression instead.
+//
+// Putting `#[builder(default)]` on the struct itself makes every field fall
+// back to its Default impl.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(default)]
+    args: Vec<String>,
+    #[builder(default)]
+    retries: u32,
+    #[builder(default = "30")]
+    timeout: u64,
+    #[builder(default = "String::from(\"/tmp\")")]
+    current_dir: String,
+    #[builder(default = "Some(\"nobody\".to_owned())")]
+    user: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(default)]
+pub struct Limits {
+    cpu: u32,
+    memory: u64,
+    #[builder(default = "8")]
+    files: u32,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert!(command.args.is_empty());
+    assert_eq!(command.retries, 0);
+    assert_eq!(command.timeout, 30);
+    assert_eq!(command.current_dir, "/tmp");
+    assert_eq!(command.user.as_deref(), Some("nobody"));
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .retries(3)
+        .timeout(5)
+        .current_dir("..".to_owned())
+        .user("root".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.retries, 3);
+    assert_eq!(command.timeout, 5);
+    assert_eq!(command.current_dir, "..");
+    assert_eq!(command.user.as_deref(), Some("root"));
+
+    let err = Command::builder().build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["executable"]);
+
+    let limits = Limits::builder().memory(512).build().unwrap();
+    assert_eq!(limits.cpu, 0);
+    assert_eq!(limits.memory, 512);
+    assert_eq!(limits.files, 8);
+}
@@ -0,0 +1,14 @@
+// A field with `each` already starts out empty, so also giving it a default is
+// contradictory. Reject the combination with an error pointing at the second
+// of the two options.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg", default)]
+    args: Vec<String>,
+}
+
+fn main() {}
@@ -0,0 +1,68 @@
+// By default the setters take `&mut self` and `build` clones every field out
+// of the builder, which rules out fields that cannot be cloned.
+//
+// Support a struct-level `#[builder(pattern = "...")]` to pick how the builder
+// is passed around:
+//
+//   - "mutable" (the default): setters take `&mut self` and return `&mut Self`,
+//     and `build(&mut self)` clones the fields.
+//
+//   - "owned": setters take `self` and return `Self`, and `build(self)` moves
+//     the fields out without cloning them.
+//
+//   - "immutable": setters take `&self` and return a modified copy of the
+//     builder, and `build(&self)` clones the fields.
+
+use derive_builder::Builder;
+use std::fs::File;
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Job {
+    name: String,
+    log: Option<File>,
+    callback: Box<dyn Fn(u32) -> u32>,
+    #[builder(each = "step")]
+    steps: Vec<Box<dyn Fn()>>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "mutable")]
+pub struct Request {
+    url: String,
+}
+
+fn main() {
+    let job = Job::builder()
+        .name("double".to_owned())
+        .callback(Box::new(|n| n * 2))
+        .step(Box::new(|| {}))
+        .build()
+        .unwrap();
+    assert_eq!(job.name, "double");
+    assert_eq!((job.callback)(21), 42);
+    assert!(job.log.is_none());
+    assert_eq!(job.steps.len(), 1);
+
+    let err = Job::builder().name("nothing".to_owned()).build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["callback"]);
+
+    let base = Command::builder().executable("cargo".to_owned());
+    let build = base.arg("build".to_owned());
+    let test = base.arg("test".to_owned());
+    assert_eq!(base.build().unwrap().args, Vec::<String>::new());
+    assert_eq!(build.build().unwrap().args, vec!["build"]);
+    assert_eq!(test.build().unwrap().args, vec!["test"]);
+
+    let mut builder = Request::builder();
+    builder.url("/".to_owned());
+    assert_eq!(builder.build().unwrap().url, "/");
+}
@@ -0,0 +1,73 @@
+// Setters that take the exact field type force callers to write conversions
+// like `.to_owned()` on every argument. With `#[builder(setter(into))]` the
+// setter becomes generic over anything convertible into the field type.
+//
+//     pub fn executable(&mut self, it: impl Into<String>) -> &mut Self {
+//         self.executable = Some(it.into());
+//         self
+//     }
+//
+// The option can be given on a single field or on the whole struct, in which
+// case an individual field can still opt out with `setter(into = false)`.
+//
+// Setters for Option<T> fields take a plain T. Use `setter(strip_option =
+// false)` to get a setter which takes the Option<T> itself.
+
+use derive_builder::Builder;
+use std::path::PathBuf;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(into))]
+    executable: String,
+    #[builder(each = "arg", setter(into))]
+    args: Vec<String>,
+    #[builder(setter(into))]
+    current_dir: Option<PathBuf>,
+    #[builder(setter(strip_option = false))]
+    user: Option<String>,
+    timeout: u64,
+}
+
+#[derive(Builder)]
+#[builder(setter(into, strip_option = false))]
+pub struct Request {
+    url: String,
+    body: Option<String>,
+    #[builder(setter(into = false, strip_option))]
+    retries: Option<u8>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo")
+        .arg("build")
+        .arg(String::from("--release"))
+        .current_dir("/tmp")
+        .user(None)
+        .timeout(10)
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir, Some(PathBuf::from("/tmp")));
+    assert_eq!(command.user, None);
+
+    let command = Command::builder()
+        .executable("cargo")
+        .user(Some("root".to_owned()))
+        .timeout(10)
+        .build()
+        .unwrap();
+    assert_eq!(command.user.as_deref(), Some("root"));
+
+    let request = Request::builder()
+        .url("/")
+        .body(Some("payload".to_owned()))
+        .retries(3)
+        .build()
+        .unwrap();
+    assert_eq!(request.url, "/");
+    assert_eq!(request.body.as_deref(), Some("payload"));
+    assert_eq!(request.retries, Some(3));
+}
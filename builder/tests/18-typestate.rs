@@ -0,0 +1,64 @@
+// A runtime error from `build` only tells the caller about a forgotten field
+// once the code actually runs. With `#[builder(typestate)]` the builder instead
+// tracks in its type which of the required fields have been set.
+//
+// Each required field gets its own type parameter on the builder, which starts
+// out as `Unset` and becomes `Set` once that field's setter is called. The
+// `build` method is only callable when every one of those parameters is `Set`,
+// and since nothing can be missing at that point it returns the struct
+// directly rather than a Result.
+//
+//     pub struct CommandBuilder<__Executable = Unset, __Timeout = Unset> {
+//         ...
+//     }
+//
+// Changing the builder's type requires consuming it, so typestate builders
+// always use the owned pattern.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    #[builder(setter(into))]
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default = "60")]
+    timeout: u64,
+    r#type: u8,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Pair<'a, T: Clone> {
+    left: &'a T,
+    right: T,
+}
+
+fn main() {
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .executable("cargo")
+        .r#type(1)
+        .current_dir("..".to_owned())
+        .build();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+    assert_eq!(command.timeout, 60);
+    assert_eq!(command.r#type, 1);
+
+    // Setting a field twice keeps the last value.
+    let command = Command::builder()
+        .r#type(1)
+        .executable("rustc")
+        .executable("cargo")
+        .build();
+    assert_eq!(command.executable, "cargo");
+
+    let left = 1;
+    let pair = Pair::builder().right(2).left(&left).build();
+    assert_eq!(*pair.left + pair.right, 3);
+}
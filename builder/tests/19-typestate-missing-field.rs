@@ -0,0 +1,25 @@
+// Forgetting a required field of a typestate builder is a compile error. The
+// message should name the field that still needs to be set rather than
+// pointing at a trait bound on some generated marker type.
+//
+//
+// Resources:
+//
+//   - Customizing the error for an unsatisfied trait bound:
+//     https://doc.rust-lang.org/reference/attributes/diagnostics.html#the-diagnosticon_unimplemented-attribute
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+    timeout: u64,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .executable("cargo".to_owned())
+        .build();
+}
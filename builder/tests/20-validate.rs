@@ -0,0 +1,110 @@
+// Some invariants span more than a single field, or can only be checked once
+// the value exists. `#[builder(build_fn(validate = "path::to::fn"))]` names a
+// function that `build` calls on the finished struct before returning it.
+//
+//     fn validate(command: &Command) -> Result<(), String>
+//
+// A validation failure is returned through the builder's error type. The
+// generated error enum accepts a String message as a ValidationError; callers
+// with their own error type convert whatever the validator returns into it.
+
+use derive_builder::Builder;
+use std::path::PathBuf;
+
+#[derive(Builder)]
+#[builder(build_fn(validate = "checks::absolute_dir"))]
+pub struct Command {
+    executable: String,
+    current_dir: PathBuf,
+}
+
+mod checks {
+    pub fn absolute_dir(command: &super::Command) -> Result<(), String> {
+        if command.current_dir.is_absolute() {
+            Ok(())
+        } else {
+            Err(format!("{} is not absolute", command.current_dir.display()))
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RangeError {
+    Incomplete(Vec<&'static str>),
+    Inverted,
+}
+
+impl From<RangeBuilderError> for RangeError {
+    fn from(err: RangeBuilderError) -> Self {
+        RangeError::Incomplete(err.missing_fields().to_vec())
+    }
+}
+
+fn ordered(range: &Range) -> Result<(), RangeError> {
+    if range.start <= range.end {
+        Ok(())
+    } else {
+        Err(RangeError::Inverted)
+    }
+}
+
+#[derive(Builder)]
+#[builder(build_fn(error = "RangeError", validate = "ordered"))]
+pub struct Range {
+    start: u32,
+    end: u32,
+}
+
+#[derive(Builder)]
+#[builder(typestate, build_fn(validate = "positive"))]
+pub struct Size {
+    width: i32,
+    height: i32,
+}
+
+fn positive(size: &Size) -> Result<(), String> {
+    if size.width > 0 && size.height > 0 {
+        Ok(())
+    } else {
+        Err("size must be positive".to_owned())
+    }
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir(PathBuf::from("/tmp"))
+        .build()
+        .unwrap();
+    assert_eq!(command.current_dir, PathBuf::from("/tmp"));
+
+    let err = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir(PathBuf::from("tmp"))
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        CommandBuilderError::ValidationError("tmp is not absolute".to_owned()),
+    );
+    assert_eq!(err.to_string(), "validation failed: tmp is not absolute");
+
+    let err = Command::builder().build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["executable", "current_dir"]);
+
+    let range = Range::builder().start(1).end(2).build().unwrap();
+    assert_eq!((range.start, range.end), (1, 2));
+    let err = Range::builder().start(2).end(1).build().err().unwrap();
+    assert_eq!(err, RangeError::Inverted);
+    let err = Range::builder().start(2).build().err().unwrap();
+    assert_eq!(err, RangeError::Incomplete(vec!["end"]));
+
+    let size = Size::builder().width(2).height(3).build().unwrap();
+    assert_eq!(size.width * size.height, 6);
+    let err = Size::builder().width(0).height(3).build().err().unwrap();
+    assert_eq!(
+        err,
+        SizeBuilderError::ValidationError("size must be positive".to_owned()),
+    );
+}
@@ -0,0 +1,92 @@
+// Deriving Builder on an enum generates one builder per variant. Each variant
+// with named fields gets a builder named after the enum and the variant, and a
+// snake_case constructor on the enum.
+//
+//     impl Shape {
+//         pub fn circle_builder() -> ShapeCircleBuilder {
+//             ...
+//         }
+//     }
+//
+// The builder's `build` method produces the enum, constructed as that
+// variant. Attributes on the enum apply to every variant's builder, and a
+// variant can add its own.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(setter(into))]
+pub enum Shape {
+    Circle {
+        radius: f64,
+        label: Option<String>,
+    },
+    #[builder(build_fn(validate = "square_corners"))]
+    RoundedRect {
+        width: f64,
+        height: f64,
+        #[builder(default)]
+        corner: f64,
+    },
+}
+
+fn square_corners(shape: &Shape) -> Result<(), String> {
+    match shape {
+        Shape::RoundedRect { width, height, corner } if corner * 2.0 > width.min(*height) => {
+            Err("corner radius too large".to_owned())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Builder)]
+pub enum Event<'a, T> {
+    Message { from: &'a str, body: T },
+    Tick { count: u64 },
+}
+
+fn main() {
+    let circle = Shape::circle_builder().radius(2.0).label("unit").build().unwrap();
+    assert_eq!(
+        circle,
+        Shape::Circle {
+            radius: 2.0,
+            label: Some("unit".to_owned()),
+        },
+    );
+
+    let rect = Shape::rounded_rect_builder()
+        .width(4.0)
+        .height(3.0)
+        .build()
+        .unwrap();
+    assert_eq!(
+        rect,
+        Shape::RoundedRect {
+            width: 4.0,
+            height: 3.0,
+            corner: 0.0,
+        },
+    );
+
+    let err = Shape::rounded_rect_builder()
+        .width(4.0)
+        .height(3.0)
+        .corner(2.0)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        ShapeRoundedRectBuilderError::ValidationError("corner radius too large".to_owned()),
+    );
+
+    let err = Shape::circle_builder().build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["radius"]);
+
+    let message = Event::message_builder().from("me").body(5).build().unwrap();
+    assert!(matches!(message, Event::Message { from: "me", body: 5 }));
+
+    let tick: Event<()> = Event::tick_builder().count(1).build().unwrap();
+    assert!(matches!(tick, Event::Tick { count: 1 }));
+}
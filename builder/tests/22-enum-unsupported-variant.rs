@@ -0,0 +1,14 @@
+// Only variants with named fields can be built. Unit and tuple variants are
+// rejected with an error pointing at each offending variant, rather than
+// silently producing a builder that has nothing to set.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub enum Message {
+    Quit,
+    Move { x: i32, y: i32 },
+    Write(String),
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+// Unions have no single set of fields that a builder could fill in, so the
+// derive refuses them outright.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub union Bits {
+    int: u32,
+    float: f32,
+}
+
+fn main() {}
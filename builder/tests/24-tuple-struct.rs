@@ -0,0 +1,43 @@
+// Tuple structs have no field names to base the setters on. Name their setters
+// after the position of the field instead, `field0`, `field1` and so on, or
+// after a name given with `#[builder(name = "...")]` on the field.
+//
+// The `build` method constructs the struct positionally. A unit struct gets a
+// builder with nothing to set whose `build` always succeeds.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Rgb(
+    #[builder(name = "red")] u8,
+    #[builder(name = "green")] u8,
+    #[builder(name = "blue")] u8,
+);
+
+#[derive(Builder)]
+pub struct Pair(String, #[builder(default)] Vec<u32>, Option<char>);
+
+#[derive(Builder)]
+pub struct Marker;
+
+fn main() {
+    let color = Rgb::builder().red(255).green(128).blue(0).build().unwrap();
+    assert_eq!((color.0, color.1, color.2), (255, 128, 0));
+
+    let err = Rgb::builder().green(1).build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["red", "blue"]);
+
+    let pair = Pair::builder()
+        .field0("key".to_owned())
+        .field2('x')
+        .build()
+        .unwrap();
+    assert_eq!(pair.0, "key");
+    assert!(pair.1.is_empty());
+    assert_eq!(pair.2, Some('x'));
+
+    let err = Pair::builder().build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["field0"]);
+
+    let Marker = Marker::builder().build().unwrap();
+}
@@ -0,0 +1,64 @@
+// The `each` attribute is not limited to Vec. Any collection that implements
+// Extend works, with the item type taken from the collection's type arguments.
+// Maps get a two argument setter taking the key and the value.
+//
+//     pub fn env(&mut self, key: String, value: String) -> &mut Self {
+//         Extend::extend(&mut self.env, iter::once((key, value)));
+//         self
+//     }
+//
+// Alongside each one-at-a-time setter there is an `extend_<field>` setter that
+// adds everything from an iterator at once. If the one-at-a-time setter has
+// the same name as the field, it is the only setter named after the field.
+
+use derive_builder::Builder;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env", setter(into))]
+    env: HashMap<String, String>,
+    #[builder(each = "limit")]
+    limits: BTreeMap<&'static str, u64>,
+    #[builder(each = "group")]
+    groups: HashSet<u32>,
+    #[builder(each = "feature")]
+    features: BTreeSet<&'static str>,
+    #[builder(each = "hook")]
+    hooks: VecDeque<fn() -> u8>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .extend_args(vec!["--release".to_owned(), "--locked".to_owned()])
+        .env("RUST_LOG", "debug")
+        .env("HOME", String::from("/root"))
+        .extend_env([("PATH".to_owned(), "/bin".to_owned())])
+        .limit("files", 1024)
+        .extend_limits([("cpu", 2)])
+        .group(10)
+        .group(10)
+        .extend_groups(vec![20, 30])
+        .feature("std")
+        .extend_features(["alloc", "std"])
+        .hook(|| 1)
+        .extend_hooks([(|| 2) as fn() -> u8])
+        .build()
+        .unwrap();
+
+    assert_eq!(command.args, ["build", "--release", "--locked"]);
+    assert_eq!(command.env.len(), 3);
+    assert_eq!(command.env["RUST_LOG"], "debug");
+    assert_eq!(command.env["PATH"], "/bin");
+    assert_eq!(command.limits, BTreeMap::from([("cpu", 2), ("files", 1024)]));
+    assert_eq!(command.groups, HashSet::from([10, 20, 30]));
+    assert_eq!(command.features, BTreeSet::from(["alloc", "std"]));
+    assert_eq!(command.hooks.iter().map(|hook| hook()).collect::<Vec<_>>(), [1, 2]);
+
+    let empty = Command::builder().build().unwrap();
+    assert!(empty.args.is_empty());
+    assert!(empty.env.is_empty());
+}
@@ -0,0 +1,14 @@
+// The `each` setter extends a collection with one item at a time, so the field
+// needs to be a collection with an item type the macro can find in its type
+// arguments. Point the error at the field's type otherwise.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: String,
+}
+
+fn main() {}
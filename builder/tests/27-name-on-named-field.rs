@@ -0,0 +1,12 @@
+// Naming a setter with `#[builder(name = "...")]` is meant for tuple struct
+// fields, which have no name of their own. Reject it on named fields.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(name = "exe")]
+    executable: String,
+}
+
+fn main() {}
@@ -0,0 +1,72 @@
+// Struct-level options are validated too, with each error pointing at the
+// option that caused it.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(patern = "owned")]
+pub struct Misspelled {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "borrowed")]
+pub struct UnknownPattern {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate, pattern = "mutable")]
+pub struct MutableTypestate {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(setter(intoo))]
+pub struct UnknownSetterOption {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(build_fn(validator = "check"))]
+pub struct UnknownBuildFnOption {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate, derive(Clone, Default))]
+pub struct TypestateDefault {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate, struct_attr(derive(Default)))]
+pub struct TypestateForwardedDefault {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate, env_prefix = "APP_")]
+pub struct TypestateEnv {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(no_std, env_prefix = "APP_")]
+pub struct NoStdEnv {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(env_separator = ";")]
+pub struct SeparatorWithoutPrefix {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(build_fn(async_finish = "connect"))]
+pub struct BorrowedAsyncFinish {
+    executable: String,
+}
+
+fn main() {}
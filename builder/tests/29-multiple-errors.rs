@@ -0,0 +1,21 @@
+// Fixing one mistake at a time is tedious. Errors from the struct's own
+// attribute and from every one of its fields are all reported together.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = 1)]
+pub struct Command {
+    #[builder(eac = "arg")]
+    args: Vec<String>,
+    #[builder(each = arg)]
+    env: Vec<String>,
+    #[builder(each = "an arg")]
+    flags: Vec<String>,
+    #[builder(default = "1 +")]
+    timeout: u64,
+    #[builder(setter(strip_option = "no"))]
+    current_dir: Option<String>,
+}
+
+fn main() {}
@@ -0,0 +1,101 @@
+// Some conversions can fail, such as narrowing an integer or parsing a
+// string. With `#[builder(setter(try_into))]` a field gets a second setter,
+// `try_<field>`, which accepts anything implementing TryInto<T> and returns
+// the builder inside a Result.
+//
+//     pub fn try_port<__Value: TryInto<u16>>(
+//         &mut self,
+//         it: __Value,
+//     ) -> Result<&mut Self, <__Value as TryInto<u16>>::Error> {
+//         ...
+//     }
+//
+// The error type is inferred from TryInto::Error, and a failed conversion
+// returns that error as it is, so nothing about it is lost. Callers convert it
+// into their own error type with `?`, alongside the error returned by
+// build(). The error does not have to implement Display or Error.
+//
+// The plain setter taking the field type is still generated. Fields using
+// `each` keep their usual collection setters.
+
+use derive_builder::Builder;
+use std::error::Error;
+use std::num::{NonZeroU8, TryFromIntError};
+
+#[derive(Builder, Debug)]
+pub struct Server {
+    host: String,
+    #[builder(setter(try_into))]
+    port: u16,
+    #[builder(setter(try_into))]
+    workers: Option<NonZeroU8>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned", setter(try_into))]
+pub struct Limits {
+    max_connections: u32,
+    #[builder(setter(try_into = false))]
+    name: String,
+}
+
+// A value whose conversion error is the value itself, which is not Display.
+#[derive(Debug, PartialEq)]
+pub struct Even(u32);
+
+impl TryFrom<Even> for u32 {
+    type Error = Even;
+
+    fn try_from(even: Even) -> Result<u32, Even> {
+        if even.0 % 2 == 0 {
+            Ok(even.0)
+        } else {
+            Err(even)
+        }
+    }
+}
+
+fn configure(port: i64, workers: u8) -> Result<Server, Box<dyn Error>> {
+    Ok(Server::builder()
+        .host("localhost".to_owned())
+        .try_port(port)?
+        .try_workers(workers)?
+        .build()?)
+}
+
+fn main() {
+    let server = configure(8080, 4).unwrap();
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.workers, NonZeroU8::new(4));
+
+    let err = configure(70000, 4).unwrap_err();
+    assert!(err.is::<TryFromIntError>());
+
+    let err = configure(80, 0).unwrap_err();
+    assert!(err.is::<TryFromIntError>());
+
+    let server = Server::builder()
+        .host("localhost".to_owned())
+        .port(80)
+        .build()
+        .unwrap();
+    assert_eq!(server.workers, None);
+
+    let limits = Limits::builder()
+        .try_max_connections(1024u64)
+        .unwrap()
+        .name("default".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(limits.max_connections, 1024);
+
+    let Err(err) = Limits::builder().try_max_connections(-1) else {
+        panic!("expected -1 to be rejected");
+    };
+    assert_eq!(err, u32::try_from(-1).unwrap_err());
+
+    let Err(err) = Limits::builder().try_max_connections(Even(3)) else {
+        panic!("expected 3 to be rejected");
+    };
+    assert_eq!(err, Even(3));
+}
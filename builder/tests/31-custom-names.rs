@@ -0,0 +1,98 @@
+// The names the derive picks, `CommandBuilder`, `Command::builder()` and
+// `build()`, may clash with items the user already has, and every generated
+// item is `pub`. All of them can be chosen at the struct level:
+//
+//     #[builder(
+//         name = "CommandConfig",
+//         vis = "pub(crate)",
+//         constructor = "configure",
+//         build_fn(name = "finish"),
+//     )]
+//
+// `vis` applies to the builder, its error type and the constructor. The error
+// type is named after the builder, here `CommandConfigError`.
+//
+// Individual setters can be renamed and given their own visibility with
+// `#[builder(setter(name = "...", vis = "..."))]`; `setter(vis)` on the struct
+// changes the default for every setter. A renamed setter also renames the
+// `try_` and `extend_` setters derived from it.
+//
+// `#[builder(setter(skip))]` removes a field's setters entirely, so the field
+// always takes its default value. Skipping a field that has no default is a
+// compile error.
+//
+// Enum variants get their own builders, so their names are chosen with the
+// same options on each variant.
+
+mod command {
+    use derive_builder::Builder;
+
+    #[derive(Builder, Debug)]
+    #[builder(
+        name = "CommandConfig",
+        vis = "pub(crate)",
+        constructor = "configure",
+        build_fn(name = "finish")
+    )]
+    pub struct Command {
+        #[builder(setter(name = "program"))]
+        pub executable: String,
+        #[builder(each = "arg", setter(name = "arguments"))]
+        pub args: Vec<String>,
+        #[builder(setter(vis = "pub(crate)", try_into))]
+        pub retries: u8,
+        #[builder(setter(skip), default = "42")]
+        pub id: u64,
+        #[builder(setter(skip))]
+        pub env: Vec<String>,
+    }
+
+    #[derive(Builder, Debug)]
+    #[builder(typestate, setter(vis = "pub(super)"), build_fn(name = "done"))]
+    pub struct Job {
+        #[builder(setter(name = "with_name"))]
+        pub name: String,
+    }
+}
+
+mod shape {
+    use derive_builder::Builder;
+
+    #[derive(Builder, Debug, PartialEq)]
+    pub enum Shape {
+        #[builder(name = "CircleSpec", constructor = "circle")]
+        Circle { radius: u32 },
+        Square { side: u32 },
+    }
+}
+
+use command::{Command, CommandConfig, CommandConfigError, Job};
+use shape::{CircleSpec, Shape};
+
+fn main() {
+    let mut config: CommandConfig = Command::configure();
+    let command = config
+        .program("cargo".to_owned())
+        .arg("build".to_owned())
+        .extend_arguments(vec!["--release".to_owned()])
+        .try_retries(3u64)
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.retries, 3);
+    assert_eq!(command.id, 42);
+    assert!(command.env.is_empty());
+
+    let err: CommandConfigError = Command::configure().retries(1).finish().unwrap_err();
+    assert_eq!(err.missing_fields(), ["executable"]);
+
+    let job = Job::builder().with_name("nightly".to_owned()).done();
+    assert_eq!(job.name, "nightly");
+
+    let mut spec: CircleSpec = Shape::circle();
+    assert_eq!(spec.radius(2).build().unwrap(), Shape::Circle { radius: 2 });
+    let square = Shape::square_builder().side(3).build().unwrap();
+    assert_eq!(square, Shape::Square { side: 3 });
+}
@@ -0,0 +1,36 @@
+// A skipped setter leaves the field at its default, so the field needs one.
+// Builder names can only be set on a struct or on individual enum variants,
+// and only fields can rename or skip their setter. A builder asking for
+// `merge` cannot also have a setter of that name.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(skip))]
+    executable: String,
+    #[builder(setter(skip))]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(name = "ShapeBuilder")]
+pub enum Shape {
+    Circle { radius: u32 },
+}
+
+#[derive(Builder)]
+#[builder(setter(name = "set"))]
+pub struct Point {
+    x: i32,
+}
+
+#[derive(Builder)]
+#[builder(merge)]
+pub struct Strategy {
+    merge: bool,
+    #[builder(each = "merge")]
+    fallbacks: Vec<String>,
+}
+
+fn main() {}
@@ -0,0 +1,95 @@
+// The builder type only gets the impls the derive generates for it. To use a
+// partially configured builder as a template, print it or compare it, list
+// the traits to derive on it:
+//
+//     #[builder(derive(Clone, Debug, PartialEq))]
+//
+// Any other attribute can be forwarded onto the builder struct with
+// `#[builder(struct_attr(...))]`, and onto the builder's storage for one field
+// with `#[builder(field_attr(...))]`.
+//
+// Every builder also implements Default, returning the same empty builder as
+// the constructor, so it can live in a Default-initialized context. Deriving
+// Default on the builder, through either `derive(...)` or
+// `struct_attr(derive(...))`, replaces the generated impl.
+
+use derive_builder::Builder;
+use std::collections::HashSet;
+
+#[derive(Builder, Debug)]
+#[builder(derive(Clone, Debug, PartialEq), struct_attr(derive(Eq, Hash)))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg", field_attr(doc = "Arguments collected so far."))]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable", derive(Clone, Debug))]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Builder)]
+#[builder(typestate, derive(Clone))]
+pub struct Job<T> {
+    name: String,
+    payload: T,
+}
+
+#[derive(Builder)]
+#[builder(struct_attr(derive(Debug, Default)))]
+pub struct Flags {
+    verbose: bool,
+}
+
+#[derive(Default)]
+pub struct Context {
+    command: CommandBuilder,
+}
+
+fn main() {
+    let mut template = Command::builder();
+    template.executable("cargo".to_owned()).arg("build".to_owned());
+
+    let mut release = template.clone();
+    release.arg("--release".to_owned());
+    assert_ne!(template, release);
+    assert_eq!(template, template.clone());
+
+    let debug = format!("{:?}", template);
+    assert!(debug.starts_with("CommandBuilder {"), "{}", debug);
+    assert!(debug.contains("\"cargo\""), "{}", debug);
+
+    let mut seen = HashSet::new();
+    seen.insert(template.clone());
+    assert!(seen.contains(&template));
+
+    let command = release.build().unwrap();
+    assert_eq!(command.args, vec!["build", "--release"]);
+    let command = template.build().unwrap();
+    assert_eq!(command.args, vec!["build"]);
+
+    let origin = Point::builder().x(0);
+    let point = origin.clone().y(1).build().unwrap();
+    assert_eq!((point.x, point.y), (0, 1));
+    assert!(format!("{:?}", origin).starts_with("PointBuilder"));
+
+    let job = JobBuilder::<u8>::default().name("nightly".to_owned());
+    let first = job.clone().payload(1).build();
+    let second = job.payload(2).build();
+    assert_eq!((first.name, first.payload), ("nightly".to_owned(), 1));
+    assert_eq!((second.name, second.payload), ("nightly".to_owned(), 2));
+
+    let flags = FlagsBuilder::default();
+    assert_eq!(format!("{:?}", flags), "FlagsBuilder { verbose: None }");
+
+    let mut context = Context::default();
+    assert_eq!(context.command, CommandBuilder::default());
+    let err = context.command.build().unwrap_err();
+    assert_eq!(err.missing_fields(), ["executable"]);
+    context.command.executable("true".to_owned());
+    assert!(context.command.build().is_ok());
+}
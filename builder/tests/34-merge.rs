@@ -0,0 +1,64 @@
+// Configuration often comes in layers, such as built-in defaults, then a
+// config file, then command line flags, each setting only some fields. With
+// `#[builder(merge)]` the builder gets a `merge` method that layers another
+// builder on top of it.
+//
+//     pub fn merge(&mut self, other: CommandBuilder) -> &mut Self {
+//         if other.executable.is_some() {
+//             self.executable = other.executable;
+//         }
+//         ...
+//         self
+//     }
+//
+// A field set in `other` replaces the value in `self`, and a field `other`
+// leaves unset keeps its value from `self`. Collections filled through `each`
+// keep the items of both builders, those of `self` first.
+//
+// The method is opt-in because a struct may have a field named `merge`, whose
+// setter would clash with it. Asking for both is an error.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(merge)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default = "30")]
+    timeout: u64,
+}
+
+#[derive(Builder, Debug)]
+pub struct Strategy {
+    merge: bool,
+}
+
+fn main() {
+    let mut defaults = Command::builder();
+    defaults
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .timeout(60);
+
+    let mut file = Command::builder();
+    file.current_dir("/src".to_owned()).timeout(120);
+
+    let mut flags = Command::builder();
+    flags.arg("--release".to_owned()).current_dir("/tmp".to_owned());
+
+    let command = defaults.merge(file).merge(flags).build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir.as_deref(), Some("/tmp"));
+    assert_eq!(command.timeout, 120);
+
+    let mut empty = Command::builder();
+    let err = empty.merge(Command::builder()).build().unwrap_err();
+    assert_eq!(err.missing_fields(), ["executable"]);
+
+    let strategy = Strategy::builder().merge(true).build().unwrap();
+    assert!(strategy.merge);
+}
@@ -0,0 +1,74 @@
+// With `#[builder(serde)]` the builder derives Serialize and Deserialize, so
+// that each layer of a configuration can be deserialized on its own and
+// merged with `#[builder(merge)]`. Every field is optional when deserializing,
+// and fields that were never set are left out when serializing. Attributes
+// for serde can be forwarded onto the builder's fields with `field_attr`.
+//
+// There is no cargo feature for this: the attribute alone opts a builder in,
+// since its field types have to implement the serde traits too. The generated
+// code names serde directly, so the crate deriving Builder needs serde, with
+// its derive feature, as a dependency of its own. Typestate builders cannot
+// use `serde`, as deserializing one could not update the state of the fields
+// it sets.
+
+use derive_builder::Builder;
+use std::collections::BTreeMap;
+
+#[derive(Builder, Debug)]
+#[builder(serde, merge)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env")]
+    env: BTreeMap<String, String>,
+    current_dir: Option<String>,
+    #[builder(default = "30", field_attr(serde(rename = "timeout_secs")))]
+    timeout: u64,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(serde)]
+pub enum Shape<T> {
+    Circle { radius: T },
+}
+
+fn main() {
+    let defaults: CommandBuilder = serde_json::from_str(
+        r#"{"executable": "cargo", "args": ["build"], "timeout_secs": 60}"#,
+    )
+    .unwrap();
+    let file: CommandBuilder =
+        serde_json::from_str(r#"{"env": {"RUST_LOG": "debug"}, "current_dir": "/src"}"#).unwrap();
+    let flags: CommandBuilder = serde_json::from_str(r#"{"args": ["--release"]}"#).unwrap();
+
+    let mut builder = defaults;
+    builder.merge(file).merge(flags);
+    let json = serde_json::to_string(&builder).unwrap();
+    assert_eq!(
+        json,
+        r#"{"executable":"cargo","args":["build","--release"],"env":{"RUST_LOG":"debug"},"current_dir":"/src","timeout_secs":60}"#,
+    );
+
+    let command = builder.build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.env["RUST_LOG"], "debug");
+    assert_eq!(command.current_dir.as_deref(), Some("/src"));
+    assert_eq!(command.timeout, 60);
+
+    let mut empty: CommandBuilder = serde_json::from_str("{}").unwrap();
+    assert_eq!(serde_json::to_string(&empty).unwrap(), r#"{"args":[],"env":{}}"#);
+    let err = empty.build().unwrap_err();
+    assert_eq!(err.missing_fields(), ["executable"]);
+
+    let mut partial = Command::builder();
+    partial.executable("ls".to_owned());
+    let json = serde_json::to_string(&partial).unwrap();
+    let mut round_trip: CommandBuilder = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&round_trip).unwrap(), json);
+    assert_eq!(round_trip.build().unwrap().timeout, 30);
+
+    let mut circle: ShapeCircleBuilder<f64> = serde_json::from_str(r#"{"radius": 1.5}"#).unwrap();
+    assert_eq!(circle.build().unwrap(), Shape::Circle { radius: 1.5 });
+}
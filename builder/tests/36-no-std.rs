@@ -0,0 +1,89 @@
+// Crates built without the standard library cannot use a builder whose
+// generated code names items through `std`. With `#[builder(no_std)]` the
+// builder only relies on `core` and `alloc`, so it works in a `#![no_std]`
+// crate that links `alloc`:
+//
+//     #![no_std]
+//     extern crate alloc;
+//
+// Its error type then implements core::error::Error in place of
+// std::error::Error.
+//
+// This test links std under a different name only to get a test harness, so
+// any path through `std` in the generated code would fail to resolve. As in
+// test 09, the prelude names are redefined to check that every item is still
+// reached through an absolute path.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as rstd;
+
+mod firmware {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use derive_builder::Builder;
+
+    #[allow(dead_code)]
+    type Option = ();
+    #[allow(dead_code)]
+    type Result = ();
+    #[allow(dead_code)]
+    type Box = ();
+
+    #[derive(Builder, Debug, PartialEq)]
+    #[builder(no_std, derive(Debug), build_fn(validate = "check"))]
+    pub struct Radio {
+        pub name: String,
+        #[builder(setter(try_into))]
+        pub channel: u8,
+        #[builder(each = "peer")]
+        pub peers: Vec<u16>,
+        pub power: core::option::Option<i8>,
+    }
+
+    fn check(radio: &Radio) -> core::result::Result<(), String> {
+        if radio.channel == 0 {
+            return core::result::Result::Err(String::from("channel 0 is reserved"));
+        }
+        core::result::Result::Ok(())
+    }
+
+    #[derive(Builder, Debug, PartialEq)]
+    #[builder(no_std, typestate)]
+    pub struct Led {
+        pub pin: u8,
+    }
+}
+
+use alloc::string::ToString;
+use firmware::{Led, Radio, RadioBuilderError};
+
+fn assert_error<E: core::error::Error>() {}
+
+fn main() {
+    assert_error::<RadioBuilderError>();
+
+    let radio = Radio::builder()
+        .name("uplink".into())
+        .try_channel(11u32)
+        .unwrap()
+        .peer(7)
+        .peer(9)
+        .build()
+        .unwrap();
+    assert_eq!(radio.channel, 11);
+    assert_eq!(radio.peers, [7, 9]);
+    assert_eq!(radio.power, None);
+
+    let err = Radio::builder().try_channel(300u32).unwrap_err();
+    assert_eq!(err, u8::try_from(300u32).unwrap_err());
+
+    let err = Radio::builder().build().unwrap_err();
+    assert_eq!(err.missing_fields(), ["name", "channel"]);
+
+    let err = Radio::builder().name("x".into()).channel(0).build().unwrap_err();
+    assert_eq!(err.to_string(), "validation failed: channel 0 is reserved");
+
+    assert_eq!(Led::builder().pin(3).build(), Led { pin: 3 });
+}
@@ -0,0 +1,162 @@
+// A field whose type also derives Builder can be configured in place through
+// that type's builder, rather than being built separately and passed in
+// whole. With `#[builder(sub_builder)]` the outer builder holds a
+// TlsConfigBuilder for the field and hands it out for mutation:
+//
+//     pub fn tls(&mut self) -> &mut TlsConfigBuilder {
+//         &mut self.tls
+//     }
+//
+// The outer build() builds the inner builder too. If that fails, the error is
+// reported as an invalid value for the field holding it:
+//
+//     invalid value for field `tls`: missing required fields: cert
+//
+// The builder type is named after the field's type. If the inner builder was
+// given another name, pass it as `sub_builder = "TlsOptions"`, or as
+// `sub_builder(builder = "TlsOptions")`. An inner build method with another
+// name is given as `sub_builder(build_fn = "finish")`.
+//
+// The inner build method has to return a Result right away, so typestate
+// builders and builders with an `async_finish` cannot be nested. Its error
+// has to implement Display, since the outer error keeps only its message. The
+// inner pattern has to be usable from the outer build(): a builder using the
+// owned pattern cannot be nested in a mutable one.
+//
+// An immutable builder only has `&self` in build(), so it clones each
+// sub-builder and builds the copy. Sub-builders nested in an immutable
+// builder must therefore implement Clone, for example through
+// `#[builder(derive(Clone))]`. They can still be configured in place through
+// the accessor, on a builder bound with `let mut`.
+//
+// Merging an outer builder merges its sub-builders too, so with
+// `#[builder(merge)]` the inner builders have to ask for `merge` as well.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(merge)]
+pub struct TlsConfig {
+    cert: String,
+    #[builder(default = "true")]
+    verify: bool,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(name = "LimitOptions", merge)]
+pub struct Limits {
+    #[builder(default = "64")]
+    connections: u32,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(merge)]
+pub struct Server {
+    host: String,
+    #[builder(sub_builder)]
+    tls: TlsConfig,
+    #[builder(sub_builder = "LimitOptions", setter(name = "limit"))]
+    limits: Limits,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(derive(Clone))]
+pub struct Endpoint {
+    url: String,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(pattern = "immutable")]
+pub struct Client {
+    #[builder(sub_builder)]
+    endpoint: Endpoint,
+    #[builder(default = "3")]
+    retries: u8,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(build_fn(name = "finish"))]
+pub struct Rollout {
+    #[builder(default = "1")]
+    batch: u32,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(pattern = "owned")]
+pub struct Deployment {
+    #[builder(sub_builder)]
+    server: Server,
+    #[builder(sub_builder(build_fn = "finish"))]
+    rollout: Rollout,
+}
+
+fn main() {
+    let mut builder = Server::builder();
+    builder.host("localhost".to_owned());
+    builder.tls().cert("server.pem".to_owned());
+    builder.limit().connections(8);
+    let server = builder.build().unwrap();
+    assert_eq!(
+        server,
+        Server {
+            host: "localhost".to_owned(),
+            tls: TlsConfig {
+                cert: "server.pem".to_owned(),
+                verify: true,
+            },
+            limits: Limits { connections: 8 },
+        },
+    );
+
+    let mut builder = Server::builder();
+    builder.host("localhost".to_owned());
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err,
+        ServerBuilderError::InvalidValue {
+            field: "tls",
+            message: "missing required fields: cert".to_owned(),
+        },
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field `tls`: missing required fields: cert",
+    );
+
+    let mut overrides = Server::builder();
+    overrides.tls().verify(false);
+    builder.tls().cert("a.pem".to_owned());
+    builder.merge(overrides);
+    let server = builder.build().unwrap();
+    assert_eq!(server.tls.cert, "a.pem");
+    assert!(!server.tls.verify);
+
+    let mut deployment = Deployment::builder();
+    deployment.server().host("example.com".to_owned());
+    let err = deployment.build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field `server`: invalid value for field `tls`: missing required fields: cert",
+    );
+
+    let mut deployment = Deployment::builder();
+    deployment.server().host("example.com".to_owned());
+    deployment.server().tls().cert("example.pem".to_owned());
+    deployment.rollout().batch(4);
+    let deployment = deployment.build().unwrap();
+    assert_eq!(deployment.server.host, "example.com");
+    assert_eq!(deployment.rollout, Rollout { batch: 4 });
+
+    let mut client = Client::builder().retries(5);
+    let err = client.build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field `endpoint`: missing required fields: url",
+    );
+    client.endpoint().url("https://example.com".to_owned());
+    let first = client.build().unwrap();
+    let second = client.retries(1).build().unwrap();
+    assert_eq!(first.endpoint.url, "https://example.com");
+    assert_eq!((first.retries, second.retries), (5, 1));
+    assert_eq!(second.endpoint, first.endpoint);
+}
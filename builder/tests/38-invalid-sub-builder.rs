@@ -0,0 +1,35 @@
+// A sub-builder is named after the struct type of its field, and it already
+// decides which of the field's values are required, so it cannot be combined
+// with `each` or `default`. An inner builder whose build method was renamed
+// needs the new name given through `sub_builder(build_fn = "...")`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct TlsConfig {
+    cert: String,
+}
+
+#[derive(Builder)]
+#[builder(build_fn(name = "finish"))]
+pub struct Limits {
+    connections: u32,
+}
+
+#[derive(Builder)]
+pub struct Proxy {
+    #[builder(sub_builder(buld_fn = "finish"))]
+    fallback_limits: Limits,
+}
+
+#[derive(Builder)]
+pub struct Server {
+    #[builder(sub_builder)]
+    tls: (String, String),
+    #[builder(sub_builder, default)]
+    fallback: TlsConfig,
+    #[builder(each = "cert", sub_builder)]
+    certs: Vec<TlsConfig>,
+}
+
+fn main() {}
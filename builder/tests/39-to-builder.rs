@@ -0,0 +1,95 @@
+// A builder can also start from an existing value, so that a base
+// configuration can be copied, tweaked and rebuilt.
+//
+//     impl From<Command> for CommandBuilder { ... }
+//
+//     impl Command {
+//         pub fn to_builder(&self) -> CommandBuilder
+//         where
+//             for<'__builder> Self: Clone,
+//         { ... }
+//     }
+//
+// Every field of the new builder is set from the value, so building it again
+// without changes gives back an equal value. to_builder() is only callable
+// when the struct is Clone; From takes the value by move.
+//
+// The bound is written with `for<'__builder>` because the derive cannot know
+// whether the struct is Clone. A plain `Self: Clone` on a struct that is not
+// Clone is an error as soon as it is written, while the higher-ranked form is
+// only checked where to_builder() is called.
+//
+// Going the other way, `TryFrom<CommandBuilder> for Command` calls build(). A
+// typestate builder whose build() cannot fail implements From instead, for
+// the builder with every required field set.
+//
+// Builders of enum variants implement TryFrom, but cannot be created from the
+// enum since it may hold another variant.
+
+use derive_builder::Builder;
+use std::convert::TryFrom;
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(sub_builder)]
+    limits: Limits,
+}
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+pub struct Limits {
+    #[builder(default = "30")]
+    timeout: u64,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(typestate)]
+pub struct Job<T> {
+    name: String,
+    payload: T,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Point(i32, i32);
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Shape {
+    Circle { radius: u32 },
+}
+
+fn main() {
+    let mut base = Command::builder();
+    base.executable("cargo".to_owned()).arg("build".to_owned());
+    let base = base.build().unwrap();
+
+    let mut release = base.to_builder();
+    release.arg("--release".to_owned()).current_dir("/tmp".to_owned());
+    release.limits().timeout(60);
+    let release = release.build().unwrap();
+    assert_eq!(release.executable, "cargo");
+    assert_eq!(release.args, vec!["build", "--release"]);
+    assert_eq!(release.current_dir.as_deref(), Some("/tmp"));
+    assert_eq!(release.limits.timeout, 60);
+
+    assert_eq!(base.to_builder().build().unwrap(), base);
+    let builder = CommandBuilder::from(base.clone());
+    assert_eq!(Command::try_from(builder).unwrap(), base);
+
+    let err = Command::try_from(Command::builder()).unwrap_err();
+    assert_eq!(err.missing_fields(), ["executable"]);
+
+    let job = Job::builder().name("nightly".to_owned()).payload(1);
+    let job = Job::from(job);
+    let retry: Job<i32> = JobBuilder::from(job).payload(2).into();
+    assert_eq!(retry, Job { name: "nightly".to_owned(), payload: 2 });
+
+    let point = PointBuilder::from(Point(1, 2)).field1(3).build().unwrap();
+    assert_eq!(point, Point(1, 3));
+
+    let mut circle = Shape::circle_builder();
+    circle.radius(2);
+    assert_eq!(Shape::try_from(circle).unwrap(), Shape::Circle { radius: 2 });
+}
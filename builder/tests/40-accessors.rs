@@ -0,0 +1,107 @@
+// Code that fills in a builder in stages sometimes needs to know what has
+// been provided so far. With `#[builder(accessors)]` every field gets read
+// accessors and a way to unset it again:
+//
+//     pub fn has_executable(&self) -> bool;
+//     pub fn get_executable(&self) -> Option<&String>;
+//     pub fn clear_executable(&mut self) -> &mut Self;
+//
+// The getter of an Option<T> field returns Option<&T>, matching its setter
+// that takes a plain T. With `setter(strip_option = false)` it returns
+// Option<&Option<T>> instead, telling a field set to None apart from one that
+// was never set.
+//
+// Fields filled through `each` always hold a collection, so their getter
+// returns it directly, and they also get `<field>_len()`. Sub-builders can be
+// read with get_<field>() and reset with clear_<field>().
+//
+// Accessors are opt-in to keep the generated code small. A single field can
+// opt in or out with `#[builder(accessors)]` or `#[builder(accessors =
+// false)]`. clear_<field>() follows the builder's pattern like the setters
+// do, and is not generated for the required fields of a typestate builder.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(accessors)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(sub_builder)]
+    limits: Limits,
+    #[builder(accessors = false, default)]
+    verbose: bool,
+}
+
+#[derive(Builder, Debug)]
+pub struct Limits {
+    #[builder(accessors)]
+    timeout: u64,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned", accessors)]
+pub struct Request {
+    url: String,
+    #[builder(setter(strip_option = false))]
+    proxy: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate, accessors)]
+pub struct Job {
+    name: String,
+    retries: Option<u8>,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    assert!(!builder.has_executable());
+    assert_eq!(builder.get_executable(), None);
+    assert!(!builder.has_args());
+    assert_eq!(builder.args_len(), 0);
+
+    builder
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .arg("--release".to_owned())
+        .current_dir("/tmp".to_owned());
+    builder.limits().timeout(30);
+    assert!(builder.has_executable());
+    assert_eq!(builder.get_executable().map(String::as_str), Some("cargo"));
+    assert!(builder.has_args());
+    assert_eq!(builder.args_len(), 2);
+    assert_eq!(builder.get_args(), &["build", "--release"]);
+    assert_eq!(builder.get_current_dir(), Some(&"/tmp".to_owned()));
+    assert_eq!(builder.get_limits().get_timeout(), Some(&30));
+
+    builder.clear_args().clear_current_dir().clear_limits();
+    assert_eq!(builder.args_len(), 0);
+    assert!(!builder.has_current_dir());
+    assert!(!builder.get_limits().has_timeout());
+    builder.limits().timeout(10);
+    let command = builder.build().unwrap();
+    assert!(command.args.is_empty());
+    assert_eq!(command.current_dir, None);
+
+    builder.clear_executable();
+    let err = builder.build().unwrap_err();
+    assert_eq!(err.missing_fields(), ["executable"]);
+
+    let request = Request::builder().url("/".to_owned());
+    assert!(request.has_url());
+    assert_eq!(request.get_proxy(), None);
+    let request = request.proxy(None);
+    assert_eq!(request.get_proxy(), Some(&None));
+    let request = request.clear_url();
+    assert!(!request.has_url());
+
+    let job = Job::builder().name("nightly".to_owned()).retries(3);
+    assert_eq!(job.get_name().map(String::as_str), Some("nightly"));
+    assert_eq!(job.get_retries(), Some(&3));
+    let job = job.clear_retries();
+    assert!(!job.has_retries());
+    assert_eq!(job.build().retries, None);
+}
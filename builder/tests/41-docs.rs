@@ -0,0 +1,96 @@
+// The builder is part of a crate's public API, so everything it adds should
+// be documented. Each setter repeats the doc comment of its field, followed by
+// a line saying whether the field is required, optional or defaulted:
+//
+//     /// Seconds to wait before giving up.
+//     ///
+//     /// If not set, defaults to `30`.
+//     pub fn timeout(&mut self, it: u64) -> &mut Self;
+//
+// The builder struct, its error type, the constructor and the build method get
+// docs of their own, so crates with `#![deny(missing_docs)]` can use the
+// derive.
+//
+// A setter is as visible as the field it sets, so a private field only gets a
+// private setter. `#[builder(setter(vis = "..."))]` still overrides this.
+
+#![deny(missing_docs)]
+
+//! A crate that documents its public items.
+
+mod command {
+    use derive_builder::Builder;
+
+    /// A program to run.
+    #[derive(Builder, Clone, Debug)]
+    #[builder(accessors)]
+    pub struct Command {
+        /// The program to run.
+        #[builder(setter(into))]
+        pub executable: String,
+        /// Arguments passed to the program.
+        #[builder(each = "arg", setter(into))]
+        pub args: Vec<String>,
+        /// Directory to run the program in.
+        pub current_dir: Option<String>,
+        /// Seconds to wait before giving up.
+        #[builder(default = "30")]
+        pub timeout: u64,
+        secret: Option<String>,
+        #[builder(setter(vis = "pub"))]
+        nice: Option<i8>,
+    }
+
+    impl Command {
+        /// The secret passed to the program, if any.
+        pub fn secret(&self) -> Option<&str> {
+            self.secret.as_deref()
+        }
+
+        /// The niceness the program runs with, if any.
+        pub fn nice(&self) -> Option<i8> {
+            self.nice
+        }
+    }
+
+    /// Sets up the command with a secret, which only this module can do.
+    pub fn with_secret(secret: &str) -> CommandBuilder {
+        let mut builder = Command::builder();
+        builder.secret(secret.to_owned());
+        builder
+    }
+
+    /// Something that can be drawn.
+    #[derive(Builder, Debug)]
+    pub enum Shape {
+        /// A circle.
+        Circle {
+            /// Its radius.
+            radius: u32,
+        },
+    }
+}
+
+use command::{Command, Shape};
+
+fn main() {
+    let command = command::with_secret("hunter2")
+        .executable("cargo")
+        .arg("build")
+        .nice(5)
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.timeout, 30);
+    assert_eq!(command.secret(), Some("hunter2"));
+    assert_eq!(command.nice(), Some(5));
+
+    let mut builder = Command::builder();
+    builder.executable("ls").current_dir("/tmp".to_owned());
+    assert!(builder.has_current_dir());
+    assert!(builder.build().is_ok());
+
+    let Shape::Circle { radius } = Shape::circle_builder().radius(2).build().unwrap();
+    assert_eq!(radius, 2);
+}
@@ -0,0 +1,19 @@
+// Setters default to the visibility of their field, so code outside the
+// module that defines a struct cannot set its private fields.
+
+mod account {
+    use derive_builder::Builder;
+
+    #[derive(Builder)]
+    pub struct Account {
+        pub name: String,
+        balance: u64,
+    }
+}
+
+fn main() {
+    let _ = account::Account::builder()
+        .name("alice".to_owned())
+        .balance(100)
+        .build();
+}
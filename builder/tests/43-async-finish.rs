@@ -0,0 +1,158 @@
+// Some values are only ready after an async step, like connecting to a server
+// or loading certificates. With `#[builder(build_fn(async_finish = "path"))]`
+// the build method becomes an async fn. It assembles and validates the struct
+// as usual, then awaits the finisher on it:
+//
+//     async fn connect(client: Client) -> Result<Client, String>;
+//
+//     pub async fn build(self) -> Result<Client, ClientBuilderError>;
+//
+// build() always consumes the builder, so that the returned future does not
+// borrow it. The option therefore requires `pattern = "owned"` or
+// `typestate`.
+//
+// A failed finisher is reported as `FinishError`, holding the Display message
+// of its error, so it is not mistaken for a failed validation. With
+// `build_fn(error = "...")` the finisher's error is instead converted into the
+// custom error type with From, keeping the original error.
+//
+// An async build cannot implement From or TryFrom for the builder, so those
+// conversions are not generated.
+//
+// No runtime is needed to drive the future; this test polls it with a waker
+// that does nothing.
+
+use derive_builder::Builder;
+use std::future::Future;
+use std::io;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned", build_fn(async_finish = "connect"))]
+pub struct Client {
+    host: String,
+    #[builder(default = "80")]
+    port: u16,
+    #[builder(setter(skip), default)]
+    connected: bool,
+}
+
+async fn connect(mut client: Client) -> Result<Client, String> {
+    Yield(false).await;
+    if client.host.is_empty() {
+        return Err("no host to connect to".to_owned());
+    }
+    client.connected = true;
+    Ok(client)
+}
+
+#[derive(Builder, Debug)]
+#[builder(typestate, build_fn(async_finish = "load"))]
+pub struct Certificate {
+    path: String,
+    #[builder(default)]
+    bytes: Vec<u8>,
+}
+
+async fn load(mut certificate: Certificate) -> Result<Certificate, String> {
+    certificate.bytes = certificate.path.bytes().rev().collect();
+    Ok(certificate)
+}
+
+#[derive(Builder, Debug)]
+#[builder(
+    pattern = "owned",
+    build_fn(error = "SessionError", async_finish = "open")
+)]
+pub struct Session {
+    user: String,
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Builder(SessionBuilderError),
+    Io(io::Error),
+}
+
+impl From<SessionBuilderError> for SessionError {
+    fn from(err: SessionBuilderError) -> Self {
+        SessionError::Builder(err)
+    }
+}
+
+impl From<io::Error> for SessionError {
+    fn from(err: io::Error) -> Self {
+        SessionError::Io(err)
+    }
+}
+
+async fn open(session: Session) -> io::Result<Session> {
+    if session.user == "root" {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "root sessions are not allowed",
+        ));
+    }
+    Ok(session)
+}
+
+// Returns Pending once, so that the executor has to poll again.
+struct Yield(bool);
+
+impl Future for Yield {
+    type Output = ();
+
+    fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+fn main() {
+    let client = block_on(Client::builder().host("localhost".to_owned()).build()).unwrap();
+    assert_eq!(client.host, "localhost");
+    assert_eq!(client.port, 80);
+    assert!(client.connected);
+
+    let err = block_on(Client::builder().host(String::new()).build()).unwrap_err();
+    assert_eq!(
+        err,
+        ClientBuilderError::FinishError("no host to connect to".to_owned())
+    );
+    assert_eq!(err.to_string(), "finishing failed: no host to connect to");
+
+    let err = block_on(Client::builder().build()).unwrap_err();
+    assert_eq!(err.missing_fields(), ["host"]);
+
+    let certificate = block_on(Certificate::builder().path("abc".to_owned()).build()).unwrap();
+    assert_eq!(certificate.bytes, b"cba");
+
+    let builder = Session::builder().user("alice".to_owned());
+    let session = block_on(builder.build()).unwrap();
+    assert_eq!(session.user, "alice");
+
+    let err = block_on(Session::builder().user("root".to_owned()).build()).unwrap_err();
+    match err {
+        SessionError::Io(err) => assert_eq!(err.kind(), io::ErrorKind::PermissionDenied),
+        SessionError::Builder(err) => panic!("unexpected error: {}", err),
+    }
+
+    let err = block_on(Session::builder().build()).unwrap_err();
+    assert!(matches!(err, SessionError::Builder(SessionBuilderError::MissingFields(_))));
+}
@@ -0,0 +1,85 @@
+// Binaries often read their configuration from environment variables. With
+// `#[builder(env_prefix = "APP_")]` the builder can load itself from them:
+//
+//     impl CommandBuilder {
+//         pub fn from_env() -> Result<Self, CommandBuilderError>;
+//         pub fn apply_env(&mut self) -> Result<&mut Self, CommandBuilderError>;
+//     }
+//
+// Each field is read from the variable named after it, so `executable` comes
+// from APP_EXECUTABLE, and parsed with FromStr. A variable that is not set
+// leaves its field as it was, so Option fields stay unset and required fields
+// are still reported by build(). Vec fields and `each` collections are split
+// on commas, or on `#[builder(env_separator = "...")]`.
+//
+// A variable that is set replaces the value its field had before. This holds
+// for lists too: the items of an `each` collection are replaced, not added to.
+//
+// A value that does not parse is reported as an InvalidValue error for its
+// field. Sub-builders, maps and fields with `setter(skip)` are not loaded.
+
+use derive_builder::Builder;
+use std::collections::BTreeSet;
+use std::env;
+
+#[derive(Builder, Debug)]
+#[builder(env_prefix = "BUILDER_TEST_")]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    #[builder(each = "feature")]
+    features: BTreeSet<String>,
+    current_dir: Option<String>,
+    #[builder(default = "30")]
+    timeout: u64,
+    r#async: Option<bool>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(env_prefix = "SERVER_TEST_", env_separator = ";")]
+pub struct Server {
+    ports: Vec<u16>,
+}
+
+fn main() {
+    env::set_var("BUILDER_TEST_EXECUTABLE", "cargo");
+    env::set_var("BUILDER_TEST_ARGS", "build,--release");
+    env::set_var("BUILDER_TEST_FEATURES", "serde,std");
+    env::set_var("BUILDER_TEST_ASYNC", "true");
+
+    let command = CommandBuilder::from_env().unwrap().timeout(5).build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.features.len(), 2);
+    assert_eq!(command.current_dir, None);
+    assert_eq!(command.timeout, 5);
+    assert_eq!(command.r#async, Some(true));
+
+    // Variables replace whatever was set before, including collected items.
+    let mut builder = Command::builder();
+    builder.executable("make".to_owned()).feature("tls".to_owned());
+    env::set_var("BUILDER_TEST_TIMEOUT", "10");
+    let command = builder.apply_env().unwrap().build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.features.len(), 2);
+    assert!(!command.features.contains("tls"));
+    assert_eq!(command.timeout, 10);
+
+    env::set_var("BUILDER_TEST_TIMEOUT", "soon");
+    let err = CommandBuilder::from_env().err().unwrap();
+    assert_eq!(
+        err,
+        CommandBuilderError::InvalidValue {
+            field: "timeout",
+            message: "BUILDER_TEST_TIMEOUT: invalid digit found in string".to_owned(),
+        }
+    );
+
+    env::set_var("SERVER_TEST_PORTS", "80;443");
+    let server = ServerBuilder::from_env().unwrap().build().unwrap();
+    assert_eq!(server.ports, [80, 443]);
+
+    env::remove_var("SERVER_TEST_PORTS");
+    let server = ServerBuilder::from_env().unwrap().build().unwrap();
+    assert!(server.ports.is_empty());
+}
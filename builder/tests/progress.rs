@@ -10,4 +10,39 @@ fn tests() {
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-struct-name.rs");
+    t.pass("tests/11-generics.rs");
+    t.pass("tests/12-error-type.rs");
+    t.pass("tests/13-missing-fields.rs");
+    t.pass("tests/14-default.rs");
+    t.compile_fail("tests/15-default-with-each.rs");
+    t.pass("tests/16-builder-pattern.rs");
+    t.pass("tests/17-setter-into.rs");
+    t.pass("tests/18-typestate.rs");
+    t.compile_fail("tests/19-typestate-missing-field.rs");
+    t.pass("tests/20-validate.rs");
+    t.pass("tests/21-enum.rs");
+    t.compile_fail("tests/22-enum-unsupported-variant.rs");
+    t.compile_fail("tests/23-union.rs");
+    t.pass("tests/24-tuple-struct.rs");
+    t.pass("tests/25-each-collections.rs");
+    t.compile_fail("tests/26-each-not-collection.rs");
+    t.compile_fail("tests/27-name-on-named-field.rs");
+    t.compile_fail("tests/28-invalid-struct-options.rs");
+    t.compile_fail("tests/29-multiple-errors.rs");
+    t.pass("tests/30-setter-try-into.rs");
+    t.pass("tests/31-custom-names.rs");
+    t.compile_fail("tests/32-invalid-names.rs");
+    t.pass("tests/33-builder-derives.rs");
+    t.pass("tests/34-merge.rs");
+    t.pass("tests/35-serde.rs");
+    t.pass("tests/36-no-std.rs");
+    t.pass("tests/37-sub-builder.rs");
+    t.compile_fail("tests/38-invalid-sub-builder.rs");
+    t.pass("tests/39-to-builder.rs");
+    t.pass("tests/40-accessors.rs");
+    t.pass("tests/41-docs.rs");
+    t.compile_fail("tests/42-private-field-setter.rs");
+    t.pass("tests/43-async-finish.rs");
+    t.pass("tests/44-env.rs");
 }
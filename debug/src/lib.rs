@@ -1,9 +1,9 @@
 use proc_macro2::{Ident, TokenStream};
-use quote::quote;
+use quote::{format_ident, quote};
 use std::str::FromStr;
 use syn::{
-    parse_macro_input, Data, DeriveInput, Expr, GenericArgument, GenericParam, Lit, Meta,
-    PathArguments, Type,
+    parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprLit, Field, Fields, GenericArgument,
+    GenericParam, Lit, Meta, PathArguments, Type,
 };
 
 #[proc_macro_derive(CustomDebug, attributes(debug))]
@@ -40,49 +40,115 @@ pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 
     let name = input.ident;
 
+    // Fields whose types decide the inferred bounds. Skipped variants are
+    // never printed, so their fields are left out.
     let mut fields = Vec::new();
-    match input.data {
+    // Problems with #[debug] attributes, all reported together.
+    let mut errors = Vec::new();
+    let body = match input.data {
         Data::Struct(ds) => {
             for f in ds.fields {
                 fields.push(f);
             }
-        }
-        Data::Enum(_) => {}
-        Data::Union(_) => {}
-    }
 
-    let field_fmt: Vec<_> = fields
-        .iter()
-        .map(|f| {
-            let mut s: Option<String> = None;
-            for attr in &f.attrs {
-                if let Meta::NameValue(val) = &attr.meta {
-                    match &val.value {
-                        Expr::Lit(exprlit) => match &exprlit.lit {
-                            Lit::Str(lit) => s = Some(lit.value()),
-                            _ => todo!("report an error"),
-                        },
-                        _ => todo!("report an error"),
-                    }
+            let mut field_fmt = Vec::new();
+            for f in &fields {
+                let field_name = &f.ident;
+                match field_value(f, quote!(&self.#field_name)) {
+                    Ok(value) => field_fmt.push(quote! {
+                        .field(stringify!(#field_name), #value)
+                    }),
+                    Err(err) => errors.push(err),
                 }
             }
 
-            let field_name = &f.ident;
-
-            match s {
-                None => {
-                    quote! {
-                        .field(stringify!(#field_name), &self.#field_name)
+            quote! {
+                f.debug_struct(stringify!(#name))
+                    #( #field_fmt )*
+                   .finish()
+            }
+        }
+        Data::Enum(de) => {
+            // Each variant is formatted the way std's derive would: unit
+            // variants as their name, tuple and struct variants through
+            // debug_tuple and debug_struct. A #[debug(skip)] variant prints
+            // only its name.
+            let mut arms = Vec::new();
+            for variant in de.variants {
+                let variant_name = &variant.ident;
+                let skipped = is_skipped(&variant.attrs).unwrap_or_else(|err| {
+                    errors.push(err);
+                    false
+                });
+                if skipped {
+                    arms.push(quote! {
+                        #name::#variant_name { .. } => f.write_str(stringify!(#variant_name)),
+                    });
+                    continue;
+                }
+                let bindings: Vec<Ident> = (0..variant.fields.len())
+                    .map(|i| format_ident!("__self_{}", i))
+                    .collect();
+                let mut values = Vec::new();
+                for (f, binding) in variant.fields.iter().zip(&bindings) {
+                    match field_value(f, quote!(#binding)) {
+                        Ok(value) => values.push(value),
+                        Err(err) => errors.push(err),
                     }
                 }
-                Some(s) => {
-                    quote! {
-                        .field(stringify!(#field_name), &format_args!(#s, &self.#field_name))
+                match &variant.fields {
+                    Fields::Named(named) => {
+                        let field_name: Vec<_> = named.named.iter().map(|f| &f.ident).collect();
+                        arms.push(quote! {
+                            #name::#variant_name { #( #field_name: #bindings, )* } => {
+                                f.debug_struct(stringify!(#variant_name))
+                                    #( .field(stringify!(#field_name), #values) )*
+                                    .finish()
+                            }
+                        });
+                    }
+                    Fields::Unnamed(_) => {
+                        arms.push(quote! {
+                            #name::#variant_name( #( #bindings, )* ) => {
+                                f.debug_tuple(stringify!(#variant_name))
+                                    #( .field(#values) )*
+                                    .finish()
+                            }
+                        });
+                    }
+                    Fields::Unit => {
+                        arms.push(quote! {
+                            #name::#variant_name => f.write_str(stringify!(#variant_name)),
+                        });
                     }
                 }
+                fields.extend(variant.fields);
             }
-        })
-        .collect();
+
+            if arms.is_empty() {
+                quote! {
+                    match *self {}
+                }
+            } else {
+                quote! {
+                    match self {
+                        #( #arms )*
+                    }
+                }
+            }
+        }
+        Data::Union(_) => quote! {
+            f.debug_struct(stringify!(#name)).finish()
+        },
+    };
+
+    let mut errors = errors.into_iter();
+    if let Some(mut error) = errors.next() {
+        for err in errors {
+            error.combine(err);
+        }
+        return error.to_compile_error().into();
+    }
 
     let generics: Vec<TokenStream> = input
         .generics
@@ -162,9 +228,7 @@ pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         where #( #assoc_typ_trait_bounds )* #( #generics_w_debug_bound )* #bound_attr
         {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                f.debug_struct(stringify!(#name))
-                    #( #field_fmt )*
-                   .finish()
+                #body
             }
         }
     };
@@ -172,6 +236,71 @@ pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     ret.into()
 }
 
+// The value passed to the formatter for a field, which goes through the
+// field's #[debug = "..."] format string if it has one.
+fn field_value(f: &Field, value: TokenStream) -> syn::Result<TokenStream> {
+    let mut s: Option<String> = None;
+    for attr in &f.attrs {
+        if !attr.path().is_ident("debug") {
+            continue;
+        }
+        match &attr.meta {
+            Meta::NameValue(val) => match &val.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) => s = Some(lit.value()),
+                value => {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "expected a format string, like `#[debug = \"{:?}\"]`",
+                    ))
+                }
+            },
+            // Only variants can be skipped, so anything else would print
+            // the field after all.
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "expected `#[debug = \"...\"]` on a field",
+                ))
+            }
+        }
+    }
+
+    Ok(match s {
+        None => value,
+        Some(s) => quote! {
+            &format_args!(#s, #value)
+        },
+    })
+}
+
+// Whether a variant is marked #[debug(skip)]. Any other #[debug] attribute on
+// a variant is an error rather than being ignored.
+fn is_skipped(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut skipped = false;
+    for attr in attrs {
+        if !attr.path().is_ident("debug") {
+            continue;
+        }
+        if !matches!(attr.meta, Meta::List(_)) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "expected `#[debug(skip)]` on a variant",
+            ));
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skipped = true;
+                Ok(())
+            } else {
+                Err(meta.error("unrecognized debug option, expected `skip`"))
+            }
+        })?;
+    }
+    Ok(skipped)
+}
+
 fn ty_mentions_generic_param(ty: &Type, param: &Ident) -> bool {
     if let Type::Path(type_path) = ty {
         if let Some(last_seg) = type_path.path.segments.last() {
@@ -0,0 +1,71 @@
+// Enums are formatted one variant at a time, the same way the standard
+// library's derive does it. Unit variants print their name, tuple variants go
+// through `debug_tuple` and struct variants through `debug_struct`:
+//
+//     match self {
+//         Shape::Empty => f.write_str("Empty"),
+//         Shape::Circle(r) => f.debug_tuple("Circle").field(r).finish(),
+//         Shape::Rect { w, h } => f.debug_struct("Rect").field("w", w).field("h", h).finish(),
+//     }
+//
+// Fields inside variants accept the same #[debug = "..."] format strings as
+// the fields of a struct. A variant marked #[debug(skip)] prints only its name
+// and none of its fields, which keeps things like secrets out of the output.
+//
+// The bounds inferred for type parameters take the fields of every variant
+// into account.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub enum Shape {
+    Empty,
+    Circle(u32),
+    Rect {
+        w: u32,
+        #[debug = "{}px"]
+        h: u32,
+    },
+    Color(#[debug = "#{:06x}"] u32, &'static str),
+}
+
+#[derive(CustomDebug)]
+pub enum Credentials<T> {
+    Anonymous,
+    #[debug(skip)]
+    Password {
+        user: String,
+        password: String,
+    },
+    Token(T),
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    assert_eq!(format!("{:?}", Shape::Empty), "Empty");
+    assert_eq!(format!("{:?}", Shape::Circle(3)), "Circle(3)");
+    assert_eq!(format!("{:?}", Shape::Rect { w: 4, h: 5 }), "Rect { w: 4, h: 5px }");
+    assert_eq!(
+        format!("{:?}", Shape::Color(0xff8800, "orange")),
+        r#"Color(#ff8800, "orange")"#
+    );
+    assert_eq!(
+        format!("{:#?}", Shape::Rect { w: 4, h: 5 }),
+        "Rect {\n    w: 4,\n    h: 5px,\n}"
+    );
+
+    let credentials = Credentials::<u64>::Password {
+        user: "alice".to_owned(),
+        password: "hunter2".to_owned(),
+    };
+    assert_eq!(format!("{:?}", credentials), "Password");
+    assert_eq!(format!("{:?}", Credentials::Token(42)), "Token(42)");
+    assert_eq!(format!("{:?}", Credentials::<u64>::Anonymous), "Anonymous");
+
+    assert_debug::<Never>();
+}
@@ -0,0 +1,25 @@
+// Mistakes in #[debug] attributes are reported where they were made, all at
+// once. A format string has to be a string literal. Only variants can be
+// skipped, and a misspelled option is an error rather than being ignored,
+// since ignoring it would print the data it was meant to hide.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Field {
+    #[debug = 5]
+    bitmask: u8,
+    #[debug(skip)]
+    secret: String,
+}
+
+#[derive(CustomDebug)]
+pub enum Credentials {
+    #[debug(skp)]
+    Password { password: String },
+    #[debug = "{}"]
+    Token(String),
+    Key(#[debug = 0x10] u64),
+}
+
+fn main() {}